@@ -0,0 +1,6 @@
+plate = circle(4):rotate(45):scale({ 3, 2 })
+hole = circle(24):scale({ 0.4, 0.4 })
+
+with_holes = plate:difference(hole:translate({ -1, 0 })):difference(hole:translate({ 1, 0 }))
+
+app:output(with_holes:extrude_linear(0.2))
@@ -0,0 +1,8 @@
+-- A block with a hole drilled through it and rounded off by a sphere
+block = cube(2, true)
+hole = cylinder(3, 0.5, 0.5, 48, true)
+ball = sphere(4):scale({ 1.3, 1.3, 1.3 })
+
+part = block:intersection(ball):difference(hole)
+
+app:output(part)
@@ -0,0 +1,7 @@
+bracket = polygon({ { 0, 0 }, { 4, 0 }, { 4, 1 }, { 1, 1 }, { 1, 3 }, { 0, 3 } })
+slot = rounded_rect(1.5, 0.5, 0.25, 8):translate({ 2.5, 0.5 })
+hole = ellipse(0.25, 0.4, 24):translate({ 0.5, 2.2 })
+
+part = bracket:difference(slot):difference(hole)
+
+app:output(part:extrude_linear(0.3))
@@ -0,0 +1,17 @@
+-- Rounded box: the hull of a sphere in each corner
+corners = {}
+
+for _, x in ipairs({ -1, 1 }) do
+    for _, y in ipairs({ -1, 1 }) do
+        for _, z in ipairs({ -0.5, 0.5 }) do
+            table.insert(corners, sphere(2):scale({ 0.3, 0.3, 0.3 }):translate({ x, y, z }))
+        end
+    end
+end
+
+box = hull(table.unpack(corners))
+
+-- Slot: the hull of two circles, cut out of the box
+slot = hull(circle(32):scale({ 0.3, 0.3 }):translate({ -0.6, 0 }), circle(32):scale({ 0.3, 0.3 }):translate({ 0.6, 0 }))
+
+app:output(box:difference(slot:extrude_linear(3)))
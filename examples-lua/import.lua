@@ -0,0 +1,7 @@
+-- Vendor parts next to our own geometry, the paths are relative to this script
+wedge = import_stl("parts/wedge.stl")
+step = import_obj("parts/step.obj"):scale({ 0.5, 0.5, 0.5 })
+
+base = cuboid({ 3, 1, 0.2 })
+
+app:output(base:union(wedge:translate({ 1, 0, 0.2 })):union(step:translate({ 2.2, 0, 0.2 })))
@@ -0,0 +1,5 @@
+profile = circle(4):rotate(45):scale({ 0.5, 1 }):translate({ 1, 0 })
+
+knob = profile:rotate_extrude(360, 48)
+
+app:output(knob)
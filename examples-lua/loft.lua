@@ -0,0 +1,3 @@
+adapter = loft({ circle(6), circle(32):scale({ 0.6, 0.6 }), circle(32):scale({ 0.6, 0.6 }) }, { 0, 1, 1.5 })
+
+app:output(adapter)
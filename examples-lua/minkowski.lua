@@ -0,0 +1,8 @@
+-- Box with rounded edges: a smaller box summed with a sphere
+rounded = minkowski(cuboid({ 3, 2, 1 }, true), sphere(3):scale({ 0.25, 0.25, 0.25 }))
+
+-- Plate with an outline that is offset around an L shape
+l_shape = polygon({ { 0, 0 }, { 2, 0 }, { 2, 1 }, { 1, 1 }, { 1, 2 }, { 0, 2 } })
+plate = l_shape:minkowski(circle(24):scale({ 0.2, 0.2 })):extrude_linear(0.5):translate({ -1, -1, -1 })
+
+app:output(rounded:union(plate))
@@ -0,0 +1,7 @@
+-- Frame with rounded outside corners and a press fit opening for a 2 x 1 part
+part = rect(2, 1, true)
+
+opening = part:offset(-0.2)
+frame = part:offset(0.5):difference(opening)
+
+app:output(frame:extrude_linear({ height = 0.3, center = false }))
@@ -0,0 +1,6 @@
+box = cuboid({ 2, 1, 0.5 }, true)
+pillar = cylinder(2, 0.5, 0.5, 32):translate({ -2, 0, 0 })
+cone = cylinder(1, 0.5, 0, 32):rotate({ 90, 0, 0 }):translate({ 2, 0, 0 })
+ring = torus(1, 0.25, 48, 16):rotate(45, { 1, 1, 0 }):translate({ 0, 3, 0 })
+
+app:output(box:concat(pillar):concat(cone):concat(ring:mirror({ 0, 1, 0 })))
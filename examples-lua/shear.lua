@@ -0,0 +1,11 @@
+-- Leaning tower: a shear along X and a lift in one step
+shear = {
+    { 1, 0, 0.3, 0 },
+    { 0, 1, 0, 0 },
+    { 0, 0, 1, 1 },
+    { 0, 0, 0, 1 },
+}
+
+tower = rect(1, 1, true):extrude_linear({ height = 4, center = false }):transform(shear)
+
+app:output(tower)
@@ -0,0 +1,9 @@
+path = {}
+for i = 0, 47 do
+    local a = i / 48 * 2 * math.pi
+    path[#path + 1] = { 3 * math.cos(a), 3 * math.sin(a), math.sin(3 * a) }
+end
+
+ring = circle(12):scale({ 0.4, 0.4 }):sweep(path, { closed = true })
+
+app:output(ring)
@@ -0,0 +1,3 @@
+vase = circle(6):extrude_linear({ height = 2, twist = 120, scale = 1.5, slices = 20 })
+
+app:output(vase)
@@ -2,7 +2,7 @@ use std::{fs::OpenOptions, path::PathBuf};
 
 use clap::{Parser, ValueEnum};
 
-use crate::{geometry::{primitives::Triangles, Geometry3D}, render::view::View};
+use crate::{export::obj::obj_parts, geometry::{primitives::Triangles, Geometry3D}, render::view::View};
 
 #[derive(ValueEnum, Clone, Debug)]
 pub enum AppMode {
@@ -16,11 +16,42 @@ impl Default for AppMode {
     }
 }
 
+/// File format of the output mode
+#[derive(ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum OutputFormat {
+    /// Binary STL
+    #[default]
+    Stl,
+    /// ASCII STL, which is larger but readable
+    StlAscii,
+    /// Wavefront OBJ, which keeps the vertices shared between the triangles
+    Obj,
+}
+
+impl OutputFormat {
+    pub fn extension(self) -> &'static str {
+        match self {
+            Self::Stl | Self::StlAscii => "stl",
+            Self::Obj => "obj",
+        }
+    }
+}
+
+/// How the geometry is written in the output mode
+#[derive(Clone, Debug)]
+pub struct OutputOptions {
+    pub format: OutputFormat,
+    /// Name of the solid in ASCII STL
+    pub name: String,
+}
+
 #[derive(Parser, Debug)]
 #[command()]
 pub struct AppArgs {
     #[arg(short, long, value_enum, default_value_t)]
     pub mode: AppMode,
+    #[arg(short, long, value_enum, default_value_t)]
+    pub format: OutputFormat,
     pub file: PathBuf,
 }
 
@@ -41,15 +72,42 @@ impl App {
         }
     }
 
+    /// Output options given on the command line, the name is the name of the script
+    pub fn output_options(&self) -> OutputOptions {
+        let name = self.args.file.file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned())
+            .unwrap_or_default();
+
+        OutputOptions {
+            format: self.args.format,
+            name,
+        }
+    }
+
     pub fn run(&self, geometry: Geometry3D) {
+        self.run_with(geometry, &self.output_options())
+    }
+
+    pub fn run_with(&self, geometry: Geometry3D, options: &OutputOptions) {
+        self.run_parts_with(vec![(None, geometry)], options)
+    }
+
+    /// Same as [`App::run_with`], but with several named parts, which stay separate in OBJ
+    pub fn run_parts(&self, parts: Vec<(String, Geometry3D)>, options: &OutputOptions) {
+        self.run_parts_with(parts.into_iter().map(|(name, part)| (Some(name), part)).collect(), options)
+    }
+
+    fn run_parts_with(&self, parts: Vec<(Option<String>, Geometry3D)>, options: &OutputOptions) {
+        let geometry = || parts.iter().fold(Geometry3D::new(vec![], vec![]), |all, (_, part)| all.concat(part));
+
         match self.args.mode {
             AppMode::View => {
                 let view = View::new(&self.title);
-                view.run(geometry);
+                view.run(geometry());
             }
             AppMode::Output => {
                 let output_dir = "out/";
-                let output_path = format!("{}/{}.stl", output_dir, self.title);
+                let output_path = format!("{}/{}.{}", output_dir, self.title, options.format.extension());
 
                 std::fs::create_dir_all(output_dir).unwrap();
 
@@ -60,7 +118,18 @@ impl App {
                     .open(output_path)
                     .unwrap();
 
-                Triangles::new(geometry.iter_triangles().collect()).stl(&mut file).unwrap();
+                match options.format {
+                    OutputFormat::Stl => {
+                        Triangles::new(geometry().iter_triangles().collect()).stl(&mut file).unwrap();
+                    }
+                    OutputFormat::StlAscii => {
+                        Triangles::new(geometry().iter_triangles().collect()).stl_ascii(&mut file, &options.name).unwrap();
+                    }
+                    OutputFormat::Obj => {
+                        let parts: Vec<_> = parts.iter().map(|(name, part)| (name.as_deref(), part)).collect();
+                        obj_parts(&mut file, &parts).unwrap();
+                    }
+                }
             }
         }
     }
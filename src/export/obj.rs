@@ -0,0 +1,36 @@
+use std::io::{BufWriter, Write};
+
+use crate::geometry::Geometry3D;
+
+impl Geometry3D {
+    /// Wavefront OBJ, where the triangles share the vertices like in the geometry
+    pub fn obj<W: std::io::Write>(&self, writer: &mut W) -> std::io::Result<()> {
+        obj_parts(writer, &[(None, self)])
+    }
+}
+
+/// Wavefront OBJ with several geometries, each named one is put into its own object
+pub fn obj_parts<W: std::io::Write>(writer: &mut W, parts: &[(Option<&str>, &Geometry3D)]) -> std::io::Result<()> {
+    let mut writer = BufWriter::new(writer);
+
+    // Indices start at 1 and go on across the objects
+    let mut first = 1;
+
+    for (name, geometry) in parts {
+        if let Some(name) = name {
+            writeln!(writer, "o {name}")?;
+        }
+
+        for vertex in geometry.vertices() {
+            writeln!(writer, "v {} {} {}", vertex.x, vertex.y, vertex.z)?;
+        }
+
+        for [a, b, c] in geometry.triangles() {
+            writeln!(writer, "f {} {} {}", a + first, b + first, c + first)?;
+        }
+
+        first += geometry.vertices().len();
+    }
+
+    writer.flush()
+}
@@ -1,5 +1,6 @@
 use std::io::{BufWriter, Write};
 use byteorder::{LittleEndian, WriteBytesExt};
+use cgmath::InnerSpace;
 
 use crate::geometry::primitives::{Triangles, P3};
 
@@ -39,4 +40,36 @@ impl Triangles<P3> {
 
         writer.flush()
     }
+
+    /// Same as [`Triangles::stl`], but readable, with `name` being the name of the solid
+    pub fn stl_ascii<W: std::io::Write>(&self, writer: &mut W, name: &str) -> std::io::Result<()> {
+        let mut writer = BufWriter::new(writer);
+
+        writeln!(writer, "solid {name}")?;
+
+        for triangle in self.iter() {
+            let a = triangle[1] - triangle[0];
+            let b = triangle[2] - triangle[0];
+            let mut normal = a.cross(b);
+
+            // Degenerate triangles keep the zero normal
+            if normal.magnitude2() > 0.0 {
+                normal = normal.normalize();
+            }
+
+            writeln!(writer, "  facet normal {} {} {}", normal.x, normal.y, normal.z)?;
+            writeln!(writer, "    outer loop")?;
+
+            for vertex in triangle.iter() {
+                writeln!(writer, "      vertex {} {} {}", vertex.x, vertex.y, vertex.z)?;
+            }
+
+            writeln!(writer, "    endloop")?;
+            writeln!(writer, "  endfacet")?;
+        }
+
+        writeln!(writer, "endsolid {name}")?;
+
+        writer.flush()
+    }
 }
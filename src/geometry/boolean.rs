@@ -1,70 +1,172 @@
+use cgmath::EuclideanSpace;
 use log::info;
 
-use super::{Geometry2D, P2};
+use super::{
+    bvh::{Aabb, Bvh},
+    predicates::{orientation, segment_intersection, Orientation, SegmentIntersection},
+    primitives::{spade_from_p2, spade_to_p2, Triangle, Triangles},
+    Geometry2D, P2, FP,
+};
 
 
-/// Check if 2 line segments intersect, return the coordinates of the intersection if yes.
-fn intersection(a: &[P2; 2], b: &[P2; 2]) -> Option<P2> {
-    // FIXME: handle collinear cases
+/// Outer edges of a geometry, indexed by their bounding boxes
+struct Boundary {
+    edges: Vec<[P2; 2]>,
+    bvh: Bvh<P2>,
+}
 
-    let slope_a = (a[1].y - a[0].y) / (a[1].x - a[0].x);
-    // Equation for the line `a`
-    let line_a = |x| a[0].y + (x - a[0].x) * slope_a;
+impl Boundary {
+    fn new(geometry: &Geometry2D) -> Self {
+        let edges = geometry.outer_edges();
+        let aabbs: Vec<_> = edges.iter().map(|e| Aabb::from_points(e)).collect();
 
-    let slope_b = (b[1].y - b[0].y) / (b[1].x - b[0].x);
-    // Equation for the line `b`
-    let line_b = |x| b[0].y + (x - b[0].x) * slope_b;
+        Self {
+            bvh: Bvh::new(&aabbs),
+            edges,
+        }
+    }
 
-    // Whether points of `b` are on different sides of the line defined by `a`
-    let crosses_a = (line_a(b[0].x) - b[0].y).signum() != (line_a(b[1].x) - b[1].y).signum();
-    // Whether points of `a` are on different sides of the line defined by `b`
-    let crosses_b = (line_b(a[0].x) - a[0].y).signum() != (line_b(a[1].x) - a[1].y).signum();
+    /// How many times the edges wind around the point.
+    /// Non-zero means that the point is inside.
+    fn winding_number(&self, point: P2) -> i32 {
+        let mut winding = 0;
 
-    if crosses_a && crosses_b {
-        let intersection_x = (b[0].y - a[0].y - b[0].x * slope_b + a[0].x * slope_a) / (slope_a - slope_b);
-        let intersection_y = line_a(intersection_x);
+        // Only the edges that cross the ray going to the right of the point matter
+        let ray = Aabb {
+            min: point,
+            max: P2::new(FP::INFINITY, point.y),
+        };
 
-        Some(P2::new(intersection_x, intersection_y))
-    } else {
-        None
+        self.bvh.query(&ray, |i| {
+            let [a, b] = self.edges[i];
+            let side = orientation(a, b, point);
+
+            if a.y <= point.y {
+                if b.y > point.y && side == Orientation::CounterClockwise {
+                    winding += 1;
+                }
+            } else if b.y <= point.y && side == Orientation::Clockwise {
+                winding -= 1;
+            }
+        });
+
+        winding
     }
 }
 
+/// Split the edges at the points where they cross, touch or overlap any of the other edges.
+/// Each intersection is computed once, so both edges get exactly the same points.
+fn split_edges(a: &Boundary, b: &Boundary) -> (Vec<[P2; 2]>, Vec<[P2; 2]>) {
+    let mut points_a: Vec<Vec<P2>> = a.edges.iter().map(|e| e.to_vec()).collect();
+    let mut points_b: Vec<Vec<P2>> = b.edges.iter().map(|e| e.to_vec()).collect();
 
-impl Geometry2D {
-    pub fn union(&self, other: &Self) -> Self {
-        let mut edges_a = self.outer_edges().into_iter().map(|e| (e, false));
-        let mut edges_b = other.outer_edges().into_iter().map(|e| (e, false));
+    for (ia, edge_a) in a.edges.iter().enumerate() {
+        b.bvh.query(&Aabb::from_points(edge_a), |ib| {
+            let edge_b = &b.edges[ib];
 
-        let mut edges_res = vec![];
+            let points = match segment_intersection(edge_a, edge_b) {
+                SegmentIntersection::Disjoint => return,
+                SegmentIntersection::Crossing(point) | SegmentIntersection::Touching(point) => vec![point],
+                SegmentIntersection::Overlapping(points) => points.to_vec(),
+            };
 
-        // 1.1. Find all intersecting edges
-        // TODO: optimize (AABB + segment tree?)
-        for (ref edge_a, ref mut edge_a_used) in &mut edges_a {
-            for (ref edge_b, ref mut edge_b_used) in &mut edges_b {
-                if let Some(point) = intersection(edge_a, edge_b) {
-                    info!("Found an intersection:\n -> {:#?}\n -> {:#?}", edge_a, edge_b);
+            info!("Found an intersection:\n -> {:#?}\n -> {:#?}", edge_a, edge_b);
 
-                    // FIXME: this should not push edges with length of 0
-                    edges_res.push([edge_a[0], point]);
-                    edges_res.push([point, edge_b[1]]);
+            points_a[ia].extend(&points);
+            points_b[ib].extend(&points);
+        });
+    }
 
-                    *edge_a_used = true;
-                    *edge_b_used = true;
-                }
+    (split_at(&a.edges, points_a), split_at(&b.edges, points_b))
+}
+
+fn split_at(edges: &[[P2; 2]], points: Vec<Vec<P2>>) -> Vec<[P2; 2]> {
+    let mut split = vec![];
+
+    for (edge, mut points) in edges.iter().zip(points) {
+        let distance = |p: &P2| (p - edge[0]).x.abs() + (p - edge[0]).y.abs();
+        points.sort_by(|a, b| distance(a).total_cmp(&distance(b)));
+        points.dedup();
+
+        for pair in points.windows(2) {
+            split.push([pair[0], pair[1]]);
+        }
+    }
+
+    split
+}
+
+
+impl Geometry2D {
+    /// Apply a boolean operation to the areas covered by the two geometries.
+    ///
+    /// All edges are put into a single constrained triangulation, and its faces
+    /// are kept depending on whether they are inside of `self` and `other`.
+    fn boolean<Op: Fn(bool, bool) -> bool>(&self, other: &Self, op: Op) -> Self {
+        use spade::{ConstrainedDelaunayTriangulation, Triangulation};
+
+        let boundary_a = Boundary::new(self);
+        let boundary_b = Boundary::new(other);
+
+        // 1. Split all intersecting edges
+        let (split_a, split_b) = split_edges(&boundary_a, &boundary_b);
+
+        // 2. Build a triangulation that has every split edge
+        let mut tri = ConstrainedDelaunayTriangulation::<spade::Point2<FP>>::new();
+
+        for [from, to] in split_a.into_iter().chain(split_b) {
+            let (Ok(from), Ok(to)) = (tri.insert(spade_from_p2(from)), tri.insert(spade_from_p2(to))) else {
+                continue;
+            };
+
+            if from != to {
+                // Splitting here only catches what's left after rounding
+                tri.add_constraint_and_split(from, to, |p| p);
             }
         }
 
-        // 1.2. Add the edges that haven't been processed
-        for (edge, used) in edges_a.chain(edges_b) {
-            if !used {
-                edges_res.push(edge);
+        // 3. Select the faces, each one of them is either fully inside or fully outside of each geometry
+        let mut triangles = vec![];
+
+        for face in tri.inner_faces() {
+            let positions = face.positions().map(spade_to_p2);
+            let center = P2::centroid(&positions);
+
+            let inside_a = boundary_a.winding_number(center) != 0;
+            let inside_b = boundary_b.winding_number(center) != 0;
+
+            if op(inside_a, inside_b) {
+                triangles.push(Triangle::from_points(positions));
             }
         }
 
-        // TODO: Rebuild the outline
+        let selected = Self::from(Triangles::new(triangles));
+
+        // 4. Rebuild the outline and triangulate it without the intermediate vertices
+        let rebuilt: anyhow::Result<Vec<Self>> = selected.polygons()
+            .into_iter()
+            .map(Self::try_from)
+            .collect();
+
+        match rebuilt {
+            Ok(parts) => parts.iter().fold(Self::new(vec![], vec![]), |all, part| all.concat(part)),
+            Err(_) => selected,
+        }
+    }
+
+    pub fn union(&self, other: &Self) -> Self {
+        self.boolean(other, |a, b| a || b)
+    }
+
+    pub fn difference(&self, other: &Self) -> Self {
+        self.boolean(other, |a, b| a && !b)
+    }
+
+    pub fn intersection(&self, other: &Self) -> Self {
+        self.boolean(other, |a, b| a && b)
+    }
 
-        // FIXME
-        self.concat(other)
+    pub fn xor(&self, other: &Self) -> Self {
+        self.boolean(other, |a, b| a != b)
     }
 }
@@ -0,0 +1,165 @@
+use cgmath::{Array, EuclideanSpace};
+
+use super::FP;
+
+
+/// Axis-aligned bounding box
+#[derive(Clone, Copy, Debug)]
+pub struct Aabb<Point> {
+    pub min: Point,
+    pub max: Point,
+}
+
+impl<Point> Aabb<Point>
+where
+    Point: EuclideanSpace<Scalar = FP>
+{
+    pub fn from_points(points: &[Point]) -> Self {
+        let mut aabb = Self {
+            min: points[0],
+            max: points[0],
+        };
+
+        for point in &points[1..] {
+            aabb = aabb.union(&Self { min: *point, max: *point });
+        }
+
+        aabb
+    }
+
+    pub fn union(&self, other: &Self) -> Self {
+        let mut min = self.min;
+        let mut max = self.max;
+
+        for i in 0..<Point as Array>::len() {
+            min[i] = min[i].min(other.min[i]);
+            max[i] = max[i].max(other.max[i]);
+        }
+
+        Self {
+            min,
+            max,
+        }
+    }
+
+    /// Whether the boxes overlap, touching counts.
+    pub fn intersects(&self, other: &Self) -> bool {
+        (0..<Point as Array>::len()).all(|i| self.min[i] <= other.max[i] && other.min[i] <= self.max[i])
+    }
+
+    pub fn center(&self) -> Point {
+        self.min.midpoint(self.max)
+    }
+}
+
+
+enum Node<Point> {
+    Leaf {
+        aabb: Aabb<Point>,
+        items: std::ops::Range<usize>,
+    },
+    Branch {
+        aabb: Aabb<Point>,
+        children: [usize; 2],
+    },
+}
+
+impl<Point: Copy> Node<Point> {
+    fn aabb(&self) -> Aabb<Point> {
+        match self {
+            Node::Leaf { aabb, .. } | Node::Branch { aabb, .. } => *aabb,
+        }
+    }
+}
+
+/// Bounding volume hierarchy, finds the items whose boxes overlap a given box.
+pub struct Bvh<Point> {
+    aabbs: Vec<Aabb<Point>>,
+    nodes: Vec<Node<Point>>,
+    /// Item indices, leaves refer to ranges of this
+    items: Vec<usize>,
+}
+
+impl<Point> Bvh<Point>
+where
+    Point: EuclideanSpace<Scalar = FP>
+{
+    const LEAF_SIZE: usize = 4;
+
+    pub fn new(aabbs: &[Aabb<Point>]) -> Self {
+        let mut bvh = Self {
+            aabbs: aabbs.to_vec(),
+            nodes: vec![],
+            items: (0..aabbs.len()).collect(),
+        };
+
+        if !aabbs.is_empty() {
+            bvh.build(aabbs, 0..aabbs.len());
+        }
+
+        bvh
+    }
+
+    /// Add the node for the `range` of items, return its index.
+    fn build(&mut self, aabbs: &[Aabb<Point>], range: std::ops::Range<usize>) -> usize {
+        let items = &mut self.items[range.clone()];
+
+        let aabb = items[1..].iter().fold(aabbs[items[0]], |aabb, i| aabb.union(&aabbs[*i]));
+
+        if items.len() <= Self::LEAF_SIZE {
+            self.nodes.push(Node::Leaf { aabb, items: range });
+            return self.nodes.len() - 1;
+        }
+
+        // Split in the middle of the longest axis
+        let size = |axis: usize| aabb.max[axis] - aabb.min[axis];
+        let axis = (0..<Point as Array>::len())
+            .max_by(|a, b| size(*a).total_cmp(&size(*b)))
+            .unwrap();
+
+        let middle = items.len() / 2;
+        items.select_nth_unstable_by(middle, |a, b| {
+            aabbs[*a].center()[axis].total_cmp(&aabbs[*b].center()[axis])
+        });
+
+        let index = self.nodes.len();
+        self.nodes.push(Node::Leaf { aabb, items: range.clone() });
+
+        let left = self.build(aabbs, range.start..range.start + middle);
+        let right = self.build(aabbs, range.start + middle..range.end);
+
+        self.nodes[index] = Node::Branch { aabb, children: [left, right] };
+
+        index
+    }
+
+    /// Call `f` with the index of every item whose box overlaps `aabb`.
+    pub fn query<F: FnMut(usize)>(&self, aabb: &Aabb<Point>, mut f: F) {
+        if self.nodes.is_empty() {
+            return;
+        }
+
+        let mut stack = vec![0];
+
+        while let Some(index) = stack.pop() {
+            let node = &self.nodes[index];
+
+            if !node.aabb().intersects(aabb) {
+                continue;
+            }
+
+            match node {
+                Node::Leaf { items, .. } => {
+                    for item in &self.items[items.clone()] {
+                        if self.aabbs[*item].intersects(aabb) {
+                            f(*item);
+                        }
+                    }
+                }
+                Node::Branch { children, .. } => {
+                    stack.extend(children);
+                }
+            }
+        }
+    }
+}
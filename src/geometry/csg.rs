@@ -0,0 +1,992 @@
+use std::collections::{HashMap, HashSet};
+
+use cgmath::{EuclideanSpace, InnerSpace};
+
+use super::{
+    bvh::{Aabb, Bvh},
+    predicates::{collinear, orientation, segment_intersection, side_of_plane, Orientation, SegmentIntersection, Side},
+    primitives::{spade_from_p2, spade_to_p2},
+    weld, Geometry3D, EPSILON, FP, P2, P3, V3,
+};
+
+
+/// Indices of the vertices of an edge, smallest first
+type Edge = [usize; 2];
+
+fn edge(a: usize, b: usize) -> Edge {
+    if a < b { [a, b] } else { [b, a] }
+}
+
+/// What a point comes from, so that all the triangles it's on share it
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+enum Key {
+    Vertex(usize),
+    /// An edge going through the plane of a triangle
+    EdgeFace(Edge, usize),
+    /// Two edges crossing each other
+    EdgeEdge(Edge, Edge),
+}
+
+impl Key {
+    fn edge_edge(a: Edge, b: Edge) -> Self {
+        if a < b { Self::EdgeEdge(a, b) } else { Self::EdgeEdge(b, a) }
+    }
+}
+
+/// Part of a triangle that a point is on
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Feature {
+    /// One of the corners
+    Vertex(usize),
+    /// Inside of the edge going from the corner to the next one
+    Edge(usize),
+    /// Inside of the triangle
+    Face,
+}
+
+impl Feature {
+    /// Whether this is on the edge going from corner `k` to the next one
+    fn on_edge(self, k: usize) -> bool {
+        match self {
+            Feature::Vertex(v) => v == k || v == (k + 1) % 3,
+            Feature::Edge(e) => e == k,
+            Feature::Face => false,
+        }
+    }
+
+    /// Where the point is, given its sides relative to each of the edges.
+    /// `inside` is the side where the triangle is, and `on` is when the point is on the line of the edge.
+    fn from_sides<S: PartialEq + Copy>(sides: [S; 3], inside: S, on: S) -> Option<Self> {
+        if sides.iter().any(|s| *s != inside && *s != on) {
+            return None;
+        }
+
+        let on_edges: Vec<usize> = (0..3).filter(|k| sides[*k] == on).collect();
+
+        match on_edges[..] {
+            [] => Some(Feature::Face),
+            [k] => Some(Feature::Edge(k)),
+            // The corner between the edges
+            [k, l] => Some(Feature::Vertex(if l == k + 1 { l } else { 0 })),
+            _ => None,
+        }
+    }
+}
+
+/// A point where two triangles meet
+#[derive(Clone, Copy, Debug)]
+struct Event {
+    point: usize,
+    /// Where the point is on each of the triangles
+    on: [Feature; 2],
+}
+
+
+/// Looks at a triangle from the side, dropping the coordinate along which its normal is the largest.
+/// Triangles with this normal are counter-clockwise in the projection.
+struct Projection {
+    axes: [usize; 2],
+    dropped: usize,
+    normal: V3,
+    origin: P3,
+}
+
+impl Projection {
+    fn new(corners: &[P3; 3]) -> Self {
+        let normal = (corners[1] - corners[0]).cross(corners[2] - corners[0]);
+
+        let dropped = (0..3)
+            .max_by(|a, b| normal[*a].abs().total_cmp(&normal[*b].abs()))
+            .unwrap();
+
+        let axes = if normal[dropped] >= 0.0 {
+            [(dropped + 1) % 3, (dropped + 2) % 3]
+        } else {
+            [(dropped + 2) % 3, (dropped + 1) % 3]
+        };
+
+        Self {
+            axes,
+            dropped,
+            normal,
+            origin: corners[0],
+        }
+    }
+
+    fn project(&self, p: P3) -> P2 {
+        P2::new(p[self.axes[0]], p[self.axes[1]])
+    }
+
+    /// Put the projected point back on the plane
+    fn lift(&self, p: P2) -> P3 {
+        let [u, v] = self.axes;
+        let d = self.dropped;
+
+        let mut lifted = self.origin;
+        lifted[u] = p.x;
+        lifted[v] = p.y;
+        lifted[d] -= (self.normal[u] * (p.x - self.origin[u]) + self.normal[v] * (p.y - self.origin[v])) / self.normal[d];
+
+        lifted
+    }
+}
+
+/// Where the point is on the triangle, the point should be on the plane of the triangle
+fn locate(corners: &[P3; 3], plane: &Projection, p: P3) -> Option<Feature> {
+    let corners = corners.map(|c| plane.project(c));
+    let p = plane.project(p);
+
+    let sides = [0, 1, 2].map(|k| orientation(corners[k], corners[(k + 1) % 3], p));
+
+    Feature::from_sides(sides, Orientation::CounterClockwise, Orientation::Collinear)
+}
+
+/// Where the segment goes through the triangle, the ends of the segment should be on different sides of it
+fn locate_crossing(from: P3, to: P3, corners: &[P3; 3]) -> Option<Feature> {
+    let sides = [0, 1, 2].map(|k| side_of_plane(from, to, corners[k], corners[(k + 1) % 3]));
+
+    let inside = *sides.iter().find(|s| **s != Side::On)?;
+
+    Feature::from_sides(sides, inside, Side::On)
+}
+
+/// Whether all the points are strictly on one side of a plane
+fn separated(sides: &[Side; 3]) -> bool {
+    sides.iter().all(|s| *s == Side::Above) || sides.iter().all(|s| *s == Side::Below)
+}
+
+/// Whether the triangle has no area, exactly
+fn degenerate(corners: &[P3; 3]) -> bool {
+    collinear(corners[0], corners[1], corners[2])
+}
+
+
+/// Triangles of two geometries, together with the points and segments where they intersect
+struct Intersections {
+    vertices: Vec<P3>,
+    /// Triangles of the first geometry, then of the second one
+    triangles: Vec<[usize; 3]>,
+    points: HashMap<Key, usize>,
+    /// Points inside of the edges
+    edge_points: HashMap<Edge, Vec<usize>>,
+    /// Points inside of each triangle
+    face_points: Vec<Vec<usize>>,
+    /// Segments that each triangle has to be split along
+    segments: Vec<Vec<[usize; 2]>>,
+}
+
+impl Intersections {
+    fn new(a: &Geometry3D, b: &Geometry3D) -> Self {
+        let offset = a.vertices.len();
+
+        let vertices: Vec<P3> = a.vertices.iter().chain(&b.vertices).cloned().collect();
+        let triangles: Vec<[usize; 3]> = a.triangles.iter()
+            .cloned()
+            .chain(b.triangles.iter().map(|t| t.map(|i| i + offset)))
+            .collect();
+
+        Self {
+            vertices,
+            face_points: vec![vec![]; triangles.len()],
+            segments: vec![vec![]; triangles.len()],
+            triangles,
+            points: HashMap::new(),
+            edge_points: HashMap::new(),
+        }
+    }
+
+    fn corners(&self, t: usize) -> [P3; 3] {
+        self.triangles[t].map(|i| self.vertices[i])
+    }
+
+    /// Index of the point, which is computed only the first time
+    fn point(&mut self, key: Key) -> usize {
+        if let Key::Vertex(v) = key {
+            return v;
+        }
+
+        if let Some(point) = self.points.get(&key) {
+            return *point;
+        }
+
+        let (position, near) = match key {
+            Key::EdgeFace(e, t) => {
+                let [u, v] = e.map(|i| self.vertices[i]);
+                let [t0, t1, t2] = self.corners(t);
+                let normal = (t1 - t0).cross(t2 - t0);
+
+                let du = normal.dot(u - t0);
+                let dv = normal.dot(v - t0);
+
+                let position = u + (v - u) * (du / (du - dv)).clamp(0.0, 1.0);
+                (position, [e[0], e[1], self.triangles[t][0], self.triangles[t][1], self.triangles[t][2]].to_vec())
+            }
+            Key::EdgeEdge(e1, e2) => {
+                let [u, v] = e1.map(|i| self.vertices[i]);
+                let [p, q] = e2.map(|i| self.vertices[i]);
+                let (d1, d2) = (v - u, q - p);
+
+                let cross = d1.cross(d2);
+                let s = (p - u).cross(d2).dot(cross) / cross.magnitude2();
+
+                (u + d1 * s.clamp(0.0, 1.0), [e1[0], e1[1], e2[0], e2[1]].to_vec())
+            }
+            Key::Vertex(_) => unreachable!(),
+        };
+
+        // Points that are too close to a vertex would only make slivers
+        let snapped = near.into_iter()
+            .map(|v| (v, (self.vertices[v] - position).magnitude()))
+            .filter(|(_, distance)| *distance < EPSILON)
+            .min_by(|(_, a), (_, b)| a.total_cmp(b));
+
+        if let Some((vertex, _)) = snapped {
+            self.points.insert(key, vertex);
+            return vertex;
+        }
+
+        self.vertices.push(position);
+        self.points.insert(key, self.vertices.len() - 1);
+
+        self.vertices.len() - 1
+    }
+
+    /// Remember the point on the triangle, so that it becomes a vertex when splitting it
+    fn place(&mut self, t: usize, on: Feature, point: usize) {
+        let corners = self.triangles[t];
+
+        match on {
+            Feature::Vertex(_) => {}
+            Feature::Edge(k) => {
+                let e = edge(corners[k], corners[(k + 1) % 3]);
+                self.edge_points.entry(e).or_default().push(point);
+            }
+            Feature::Face => {
+                // After rounding, the point can end up on an edge or even outside of the triangle.
+                // It is then put on that edge, so that the triangle on the other side gets it too.
+                let corners = self.corners(t);
+                let plane = Projection::new(&corners);
+                let [c0, c1, c2] = corners.map(|c| plane.project(c));
+                let p = plane.project(self.vertices[point]);
+
+                let outside = [(c0, c1), (c1, c2), (c2, c0)].iter()
+                    .position(|(a, b)| orientation(*a, *b, p) != Orientation::CounterClockwise);
+
+                match outside {
+                    Some(k) => self.place(t, Feature::Edge(k), point),
+                    None => self.face_points[t].push(point),
+                }
+            }
+        }
+    }
+
+    /// Remember that the triangle has to be split along the segment, `side` tells which triangle of the events it is
+    fn connect(&mut self, t: usize, side: usize, from: &Event, to: &Event) {
+        // Segments along the edges are there anyway
+        if (0..3).any(|k| from.on[side].on_edge(k) && to.on[side].on_edge(k)) {
+            return;
+        }
+
+        if self.vertices[from.point] == self.vertices[to.point] {
+            return;
+        }
+
+        self.segments[t].push([from.point, to.point]);
+    }
+
+    /// Find the points and segments where the triangles `ta` and `tb` meet
+    fn intersect(&mut self, ta: usize, tb: usize) {
+        let a = self.corners(ta);
+        let b = self.corners(tb);
+
+        let sides_a = a.map(|p| side_of_plane(b[0], b[1], b[2], p));
+        let sides_b = b.map(|p| side_of_plane(a[0], a[1], a[2], p));
+
+        if separated(&sides_a) || separated(&sides_b) {
+            return;
+        }
+
+        let coplanar = sides_b.iter().all(|s| *s == Side::On);
+
+        let mut events = if coplanar {
+            self.coplanar_events(ta, tb)
+        } else {
+            let mut events = self.crossing_events(ta, tb, &sides_a);
+            let swapped = self.crossing_events(tb, ta, &sides_b);
+            events.extend(swapped.into_iter().map(|e| Event { point: e.point, on: [e.on[1], e.on[0]] }));
+            events
+        };
+
+        events.sort_by_key(|e| e.point);
+        events.dedup_by_key(|e| e.point);
+
+        for event in &events {
+            self.place(ta, event.on[0], event.point);
+            self.place(tb, event.on[1], event.point);
+        }
+
+        if coplanar {
+            // Each triangle is split along the edges of the other one
+            for (t, side) in [(ta, 0), (tb, 1)] {
+                let other = 1 - side;
+                let corners = if side == 0 { b } else { a };
+
+                for (k, start) in corners.iter().enumerate() {
+                    let mut along: Vec<Event> = events.iter().filter(|e| e.on[other].on_edge(k)).cloned().collect();
+
+                    along.sort_by(|x, y| {
+                        let dx = (self.vertices[x.point] - start).magnitude2();
+                        let dy = (self.vertices[y.point] - start).magnitude2();
+                        dx.total_cmp(&dy)
+                    });
+
+                    for pair in along.windows(2) {
+                        self.connect(t, side, &pair[0], &pair[1]);
+                    }
+                }
+            }
+        } else {
+            // All the points are on the line where the planes of the triangles meet
+            let normal = |c: &[P3; 3]| (c[1] - c[0]).cross(c[2] - c[0]);
+            let direction = normal(&a).cross(normal(&b));
+
+            events.sort_by(|x, y| {
+                let dx = self.vertices[x.point].to_vec().dot(direction);
+                let dy = self.vertices[y.point].to_vec().dot(direction);
+                dx.total_cmp(&dy)
+            });
+
+            for pair in events.windows(2) {
+                self.connect(ta, 0, &pair[0], &pair[1]);
+                self.connect(tb, 1, &pair[0], &pair[1]);
+            }
+        }
+    }
+
+    /// Points where the edges of `tx` go through `ty`, which is on a different plane.
+    /// `sides` are the sides of the plane of `ty` that the corners of `tx` are on.
+    fn crossing_events(&mut self, tx: usize, ty: usize, sides: &[Side; 3]) -> Vec<Event> {
+        let x = self.corners(tx);
+        let y = self.corners(ty);
+        let (ix, iy) = (self.triangles[tx], self.triangles[ty]);
+
+        let plane_y = Projection::new(&y);
+        let edge_y = |j: usize| edge(iy[j], iy[(j + 1) % 3]);
+
+        let mut events = vec![];
+
+        for k in 0..3 {
+            let l = (k + 1) % 3;
+            let edge_x = edge(ix[k], ix[l]);
+
+            if sides[k] == Side::On {
+                if let Some(on_y) = locate(&y, &plane_y, x[k]) {
+                    events.push(Event { point: ix[k], on: [Feature::Vertex(k), on_y] });
+                }
+            }
+
+            if sides[k] != Side::On && sides[l] != Side::On && sides[k] != sides[l] {
+                if let Some(on_y) = locate_crossing(x[k], x[l], &y) {
+                    let key = match on_y {
+                        Feature::Vertex(j) => Key::Vertex(iy[j]),
+                        Feature::Edge(j) => Key::edge_edge(edge_x, edge_y(j)),
+                        Feature::Face => Key::EdgeFace(edge_x, ty),
+                    };
+
+                    events.push(Event { point: self.point(key), on: [Feature::Edge(k), on_y] });
+                }
+            } else if sides[k] == Side::On && sides[l] == Side::On {
+                // The edge is on the plane of `ty`, and can cross its edges
+                let segment = [plane_y.project(x[k]), plane_y.project(x[l])];
+
+                for j in 0..3 {
+                    let other = [plane_y.project(y[j]), plane_y.project(y[(j + 1) % 3])];
+
+                    if let SegmentIntersection::Crossing(_) = segment_intersection(&segment, &other) {
+                        let point = self.point(Key::edge_edge(edge_x, edge_y(j)));
+                        events.push(Event { point, on: [Feature::Edge(k), Feature::Edge(j)] });
+                    }
+                }
+            }
+        }
+
+        events
+    }
+
+    /// Points where the triangles `ta` and `tb`, which are on the same plane, overlap
+    fn coplanar_events(&mut self, ta: usize, tb: usize) -> Vec<Event> {
+        let a = self.corners(ta);
+        let b = self.corners(tb);
+        let (ia, ib) = (self.triangles[ta], self.triangles[tb]);
+
+        let plane = Projection::new(&a);
+        let plane_b = Projection::new(&b);
+
+        let mut events = vec![];
+
+        for k in 0..3 {
+            if let Some(on_b) = locate(&b, &plane_b, a[k]) {
+                events.push(Event { point: ia[k], on: [Feature::Vertex(k), on_b] });
+            }
+            if let Some(on_a) = locate(&a, &plane, b[k]) {
+                events.push(Event { point: ib[k], on: [on_a, Feature::Vertex(k)] });
+            }
+        }
+
+        for k in 0..3 {
+            let segment_a = [plane.project(a[k]), plane.project(a[(k + 1) % 3])];
+
+            for j in 0..3 {
+                let segment_b = [plane.project(b[j]), plane.project(b[(j + 1) % 3])];
+
+                if let SegmentIntersection::Crossing(_) = segment_intersection(&segment_a, &segment_b) {
+                    let key = Key::edge_edge(edge(ia[k], ia[(k + 1) % 3]), edge(ib[j], ib[(j + 1) % 3]));
+                    events.push(Event { point: self.point(key), on: [Feature::Edge(k), Feature::Edge(j)] });
+                }
+            }
+        }
+
+        events
+    }
+
+    /// Triangulate the triangle together with all the points and segments that were found on it
+    fn split(&mut self, t: usize) -> Vec<[usize; 3]> {
+        use spade::{handles::FixedVertexHandle, ConstrainedDelaunayTriangulation, Triangulation};
+
+        let corners = self.triangles[t];
+        let face_points = std::mem::take(&mut self.face_points[t]);
+        let segments = std::mem::take(&mut self.segments[t]);
+
+        // Points along each edge, from its first corner to the next one
+        let chains: Vec<Vec<usize>> = (0..3).map(|k| {
+            let (from, to) = (corners[k], corners[(k + 1) % 3]);
+            let start = self.vertices[from];
+
+            let mut points = self.edge_points.get(&edge(from, to)).cloned().unwrap_or_default();
+            points.sort_by(|a, b| {
+                (self.vertices[*a] - start).magnitude2().total_cmp(&(self.vertices[*b] - start).magnitude2())
+            });
+            points.dedup();
+            points.retain(|p| *p != from && *p != to);
+
+            std::iter::once(from).chain(points).chain(std::iter::once(to)).collect()
+        }).collect();
+
+        if chains.iter().all(|c| c.len() == 2) && face_points.is_empty() && segments.is_empty() {
+            return vec![corners];
+        }
+
+        let plane = Projection::new(&self.corners(t));
+        let mut tri = ConstrainedDelaunayTriangulation::<spade::Point2<FP>>::new();
+
+        // Point index and positions along the edges for each vertex of the triangulation
+        let mut points: Vec<Option<usize>> = vec![];
+        let mut along: Vec<Vec<(usize, usize)>> = vec![];
+
+        let mut insert = |tri: &mut ConstrainedDelaunayTriangulation<_>, point: usize| -> Option<FixedVertexHandle> {
+            let handle = tri.insert(spade_from_p2(plane.project(self.vertices[point]))).ok()?;
+
+            if points.len() <= handle.index() {
+                points.resize(handle.index() + 1, None);
+                along.resize(handle.index() + 1, vec![]);
+            }
+            points[handle.index()].get_or_insert(point);
+
+            Some(handle)
+        };
+
+        let mut chain_handles = vec![];
+        for chain in &chains {
+            let handles: Vec<_> = chain.iter().filter_map(|p| insert(&mut tri, *p)).collect();
+            chain_handles.push(handles);
+        }
+        for point in &face_points {
+            insert(&mut tri, *point);
+        }
+        let segment_handles: Vec<_> = segments.iter()
+            .filter_map(|[from, to]| Some([insert(&mut tri, *from)?, insert(&mut tri, *to)?]))
+            .collect();
+
+        for (k, handles) in chain_handles.iter().enumerate() {
+            for (order, handle) in handles.iter().enumerate() {
+                along[handle.index()].push((k, order));
+            }
+
+            for pair in handles.windows(2) {
+                if pair[0] == pair[1] {
+                    continue;
+                }
+
+                // The points along an edge only go one way, unless rounding says otherwise
+                if tri.can_add_constraint(pair[0], pair[1]) {
+                    tri.add_constraint(pair[0], pair[1]);
+                }
+            }
+        }
+
+        for [from, to] in segment_handles {
+            if from != to {
+                tri.add_constraint_and_split(from, to, |p| p);
+            }
+        }
+
+        // The edges are straight only up to rounding, so the triangulation can have slivers outside of them.
+        // Such a sliver has all its vertices on one edge and goes around in the opposite direction.
+        let inside = |vertices: [FixedVertexHandle; 3]| (0..3).all(|k| {
+            let orders: Option<Vec<usize>> = vertices.iter()
+                .map(|v| along.get(v.index())?.iter().find(|(e, _)| *e == k).map(|(_, order)| *order))
+                .collect();
+
+            match orders.as_deref() {
+                // Increasing after some rotation
+                Some(&[o0, o1, o2]) => [(o0, o1), (o1, o2), (o2, o0)].iter().filter(|(x, y)| x < y).count() == 2,
+                _ => true,
+            }
+        });
+
+        let faces: Vec<_> = tri.inner_faces()
+            .map(|f| f.vertices().map(|v| v.fix()))
+            .filter(|vertices| inside(*vertices))
+            .collect();
+
+        faces.into_iter().map(|vertices| vertices.map(|v| {
+            match points.get(v.index()).cloned().flatten() {
+                Some(point) => point,
+                None => {
+                    // Added when splitting crossing segments
+                    self.vertices.push(plane.lift(spade_to_p2(*tri.vertex(v).data())));
+                    let point = self.vertices.len() - 1;
+
+                    if points.len() <= v.index() {
+                        points.resize(v.index() + 1, None);
+                    }
+                    points[v.index()] = Some(point);
+
+                    point
+                }
+            }
+        })).collect()
+    }
+}
+
+
+/// Where a part of the surface of one geometry is relative to the other geometry
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Location {
+    Inside,
+    Outside,
+    /// On the surface of the other geometry, facing the same way
+    Same,
+    /// On the surface of the other geometry, facing the other way
+    Opposite,
+}
+
+/// Generalized winding number, close to 1 inside of a closed geometry and to 0 outside of it.
+/// From "Robust Inside-Outside Segmentation using Generalized Winding Numbers" by Alec Jacobson et al.
+fn winding_number(geometry: &Geometry3D, point: P3) -> FP {
+    let mut solid_angle = 0.0;
+
+    for t in &geometry.triangles {
+        let [a, b, c] = t.map(|i| geometry.vertices[i] - point);
+        let [la, lb, lc] = [a, b, c].map(|v| v.magnitude());
+
+        let det = a.dot(b.cross(c));
+        let div = la * lb * lc + a.dot(b) * lc + b.dot(c) * la + c.dot(a) * lb;
+
+        solid_angle += 2.0 * det.atan2(div);
+    }
+
+    solid_angle / (4.0 * std::f64::consts::PI)
+}
+
+/// Groups of triangles that are connected without crossing the `boundary` edges
+fn patches(triangles: &[[usize; 3]], boundary: &HashSet<Edge>) -> Vec<Vec<usize>> {
+    fn root(parents: &mut [usize], mut i: usize) -> usize {
+        while parents[i] != i {
+            parents[i] = parents[parents[i]];
+            i = parents[i];
+        }
+        i
+    }
+
+    let mut parents: Vec<usize> = (0..triangles.len()).collect();
+    let mut by_edge: HashMap<Edge, usize> = HashMap::new();
+
+    for (i, t) in triangles.iter().enumerate() {
+        for k in 0..3 {
+            let e = edge(t[k], t[(k + 1) % 3]);
+
+            if boundary.contains(&e) {
+                continue;
+            }
+
+            if let Some(other) = by_edge.insert(e, i) {
+                let (ra, rb) = (root(&mut parents, i), root(&mut parents, other));
+                parents[ra] = rb;
+            }
+        }
+    }
+
+    let mut groups: HashMap<usize, Vec<usize>> = HashMap::new();
+    for i in 0..triangles.len() {
+        groups.entry(root(&mut parents, i)).or_default().push(i);
+    }
+
+    groups.into_values().collect()
+}
+
+/// Split the triangles at the vertices that ended up on their edges, because of rounding.
+/// Such an edge is only on one side, while the other side goes through the vertex.
+fn close_cracks(vertices: &[P3], triangles: &mut Vec<[usize; 3]>) {
+    loop {
+        let mut count: HashMap<[usize; 2], isize> = HashMap::new();
+        for t in triangles.iter() {
+            for k in 0..3 {
+                *count.entry([t[k], t[(k + 1) % 3]]).or_default() += 1;
+                *count.entry([t[(k + 1) % 3], t[k]]).or_default() -= 1;
+            }
+        }
+
+        let open: Vec<[usize; 2]> = count.into_iter()
+            .filter(|(_, c)| *c > 0)
+            .map(|(e, _)| e)
+            .collect();
+
+        let ends: HashSet<usize> = open.iter().flatten().cloned().collect();
+
+        let mut split = false;
+
+        for [a, b] in open {
+            let (pa, pb) = (vertices[a], vertices[b]);
+            let along = pb - pa;
+
+            let on_edge = ends.iter()
+                .filter(|v| **v != a && **v != b)
+                .map(|v| (*v, (vertices[*v] - pa).dot(along) / along.magnitude2()))
+                .filter(|(v, s)| *s > 0.0 && *s < 1.0 && (pa + along * *s - vertices[*v]).magnitude() < EPSILON)
+                .min_by(|(_, s1), (_, s2)| s1.total_cmp(s2));
+
+            let Some((v, _)) = on_edge else {
+                continue;
+            };
+
+            let Some(i) = triangles.iter().position(|t| (0..3).any(|k| t[k] == a && t[(k + 1) % 3] == b)) else {
+                continue;
+            };
+
+            let t = triangles[i];
+            let k = (0..3).find(|k| t[*k] == a).unwrap();
+            let c = t[(k + 2) % 3];
+
+            triangles[i] = [a, v, c];
+            triangles.push([v, b, c]);
+            split = true;
+        }
+
+        if !split {
+            break;
+        }
+    }
+}
+
+/// Remove the pairs of triangles that have the same corners but face the other way.
+/// They are slivers on both sides of an edge that a vertex is on, up to rounding, and enclose nothing.
+fn drop_opposites(triangles: &mut Vec<[usize; 3]>) {
+    // The same key for the triangle whichever corner it starts from
+    let key = |[a, b, c]: [usize; 3]| {
+        let k = if a < b && a < c { 0 } else if b < c { 1 } else { 2 };
+        [[a, b, c], [b, c, a], [c, a, b]][k]
+    };
+
+    let mut count: HashMap<[usize; 3], usize> = HashMap::new();
+    for t in triangles.iter() {
+        *count.entry(key(*t)).or_default() += 1;
+    }
+
+    let mut dropped: HashMap<[usize; 3], usize> = HashMap::new();
+    triangles.retain(|[a, b, c]| {
+        let (this, opposite) = (key([*a, *b, *c]), key([*a, *c, *b]));
+        let pairs = count[&this].min(count.get(&opposite).copied().unwrap_or(0));
+        let seen = dropped.entry(this).or_default();
+
+        *seen += 1;
+        *seen > pairs
+    });
+}
+
+/// Round the coordinates to a fine grid, so that the surfaces which are meant to be on the same plane,
+/// but are off by a rounding error after a rotation, are exactly on it
+fn snap(geometry: &Geometry3D) -> Geometry3D {
+    const GRID: FP = (1u64 << 32) as FP;
+
+    geometry.map_vertices(|p| p.map(|x| (x * GRID).round() / GRID), false)
+}
+
+fn edges(triangles: &[[usize; 3]]) -> HashSet<Edge> {
+    triangles.iter()
+        .flat_map(|t| (0..3).map(|k| edge(t[k], t[(k + 1) % 3])))
+        .collect()
+}
+
+
+impl Geometry3D {
+    /// Apply a boolean operation to the volumes enclosed by the two geometries.
+    ///
+    /// The triangles of both geometries are split where they intersect, and the resulting parts of
+    /// the surfaces are kept depending on where they are relative to the other geometry.
+    fn boolean(&self, other: &Self, keep_self: &[Location], keep_other: &[Location], flip_other: bool) -> Self {
+        let (this, other) = (snap(self), snap(other));
+        let mut intersections = Intersections::new(&this, &other);
+        let first = self.triangles.len();
+
+        // 1. Find where the triangles intersect
+        let aabb = |intersections: &Intersections, t: usize| Aabb::from_points(&intersections.corners(t));
+
+        let others: Vec<usize> = (first..intersections.triangles.len())
+            .filter(|t| !degenerate(&intersections.corners(*t)))
+            .collect();
+        let bvh = Bvh::new(&others.iter().map(|t| aabb(&intersections, *t)).collect::<Vec<_>>());
+
+        for ta in 0..first {
+            if degenerate(&intersections.corners(ta)) {
+                continue;
+            }
+
+            let mut candidates = vec![];
+            bvh.query(&aabb(&intersections, ta), |i| candidates.push(others[i]));
+
+            for tb in candidates {
+                intersections.intersect(ta, tb);
+            }
+        }
+
+        // 2. Split the triangles where they intersect
+        let mut pieces = [vec![], vec![]];
+        for t in 0..intersections.triangles.len() {
+            let part = if t < first { 0 } else { 1 };
+            pieces[part].extend(intersections.split(t));
+        }
+
+        // 3. Merge the points that ended up in the same place
+        let (vertices, indices) = weld(&intersections.vertices);
+        let pieces = pieces.map(|triangles| -> Vec<[usize; 3]> {
+            triangles.into_iter()
+                .map(|t| t.map(|i| indices[i]))
+                .filter(|[t0, t1, t2]| t0 != t1 && t1 != t2 && t2 != t0)
+                .collect()
+        });
+
+        if vertices.is_empty() {
+            return Self::new(vec![], vec![]);
+        }
+
+        // 4. Select the parts of the surfaces, their boundaries are where the surfaces intersect
+        let aabb = Aabb::from_points(&vertices);
+        let delta = ((aabb.max - aabb.min).magnitude() * 1e-6).max(EPSILON);
+
+        let locate = |triangles: &[[usize; 3]], patch: &[usize], geometry: &Self| -> Option<Location> {
+            let corners = |t: usize| triangles[t].map(|i| vertices[i]);
+            let area = |t: usize| {
+                let [a, b, c] = corners(t);
+                (b - a).cross(c - a).magnitude()
+            };
+
+            let largest = *patch.iter().max_by(|a, b| area(**a).total_cmp(&area(**b)))?;
+
+            if area(largest) == 0.0 {
+                return None;
+            }
+
+            let [a, b, c] = corners(largest);
+            let normal = (b - a).cross(c - a).normalize();
+            let center = P3::centroid(&[a, b, c]);
+
+            let front = winding_number(geometry, center + normal * delta) > 0.5;
+            let back = winding_number(geometry, center - normal * delta) > 0.5;
+
+            Some(match (front, back) {
+                (false, false) => Location::Outside,
+                (true, true) => Location::Inside,
+                (false, true) => Location::Same,
+                (true, false) => Location::Opposite,
+            })
+        };
+
+        let mut triangles = vec![];
+
+        let boundaries = [edges(&pieces[1]), edges(&pieces[0])];
+        let settings = [(&other, keep_self, false), (&this, keep_other, flip_other)];
+
+        for (part, (geometry, keep, flip)) in settings.into_iter().enumerate() {
+            for patch in patches(&pieces[part], &boundaries[part]) {
+                let Some(location) = locate(&pieces[part], &patch, geometry) else {
+                    continue;
+                };
+
+                if !keep.contains(&location) {
+                    continue;
+                }
+
+                for t in patch {
+                    let [t0, t1, t2] = pieces[part][t];
+                    triangles.push(if flip { [t0, t2, t1] } else { [t0, t1, t2] });
+                }
+            }
+        }
+
+        close_cracks(&vertices, &mut triangles);
+        drop_opposites(&mut triangles);
+
+        // Drop the vertices that are not used anymore
+        let mut used: Vec<Option<usize>> = vec![None; vertices.len()];
+        let mut kept = vec![];
+
+        for t in &mut triangles {
+            for i in t.iter_mut() {
+                *i = *used[*i].get_or_insert_with(|| {
+                    kept.push(vertices[*i]);
+                    kept.len() - 1
+                });
+            }
+        }
+
+        Self::new(kept, triangles)
+    }
+
+    pub fn union(&self, other: &Self) -> Self {
+        self.boolean(other, &[Location::Outside, Location::Same], &[Location::Outside], false)
+    }
+
+    pub fn difference(&self, other: &Self) -> Self {
+        self.boolean(other, &[Location::Outside, Location::Opposite], &[Location::Inside], true)
+    }
+
+    pub fn intersection(&self, other: &Self) -> Self {
+        self.boolean(other, &[Location::Inside, Location::Same], &[Location::Inside], false)
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use cgmath::{Deg, EuclideanSpace, InnerSpace};
+
+    use super::*;
+
+    fn volume(geometry: &Geometry3D) -> FP {
+        geometry.triangles.iter()
+            .map(|t| {
+                let [a, b, c] = t.map(|i| geometry.vertices[i].to_vec());
+                a.dot(b.cross(c)) / 6.0
+            })
+            .sum()
+    }
+
+    /// Every edge is used once in each direction, so the surface has no cracks or overlaps
+    fn assert_closed(geometry: &Geometry3D) {
+        let mut directed = HashMap::new();
+
+        for t in &geometry.triangles {
+            for k in 0..3 {
+                *directed.entry([t[k], t[(k + 1) % 3]]).or_insert(0) += 1;
+            }
+        }
+
+        for ([a, b], count) in &directed {
+            assert_eq!(*count, 1, "Edge {a}-{b} is used {count} times");
+            assert_eq!(directed.get(&[*b, *a]), Some(&1), "Edge {a}-{b} has no opposite");
+        }
+    }
+
+    /// Volumes of the union, intersection and difference add up with the volumes of the inputs
+    fn assert_identities(a: &Geometry3D, b: &Geometry3D) -> [FP; 3] {
+        let union = a.union(b);
+        let intersection = a.intersection(b);
+        let difference = a.difference(b);
+
+        for result in [&union, &intersection, &difference] {
+            assert_closed(result);
+        }
+
+        let [u, i, d] = [&union, &intersection, &difference].map(volume);
+        let (va, vb) = (volume(a), volume(b));
+        let tolerance = 1e-6 * (va + vb);
+
+        assert!((u + i - va - vb).abs() < tolerance, "Union {u} and intersection {i} of volumes {va} and {vb}");
+        assert!((d + i - va).abs() < tolerance, "Difference {d} and intersection {i} of volume {va}");
+
+        [u, i, d]
+    }
+
+    fn assert_near(actual: FP, expected: FP) {
+        assert!((actual - expected).abs() < 1e-9, "Expected {expected}, got {actual}");
+    }
+
+    #[test]
+    fn overlapping_cubes() {
+        let a = Geometry3D::cube(2.0, false);
+        let b = a.translate(V3::new(1.0, 1.0, 1.0));
+
+        let [u, i, d] = assert_identities(&a, &b);
+        assert_near(u, 15.0);
+        assert_near(i, 1.0);
+        assert_near(d, 7.0);
+    }
+
+    #[test]
+    fn coplanar_faces() {
+        // Touching side by side
+        let a = Geometry3D::cube(1.0, false);
+        let b = a.translate(V3::new(1.0, 0.0, 0.0));
+
+        let [u, i, d] = assert_identities(&a, &b);
+        assert_near(u, 2.0);
+        assert_near(i, 0.0);
+        assert_near(d, 1.0);
+
+        // Corner cut out, sharing three faces
+        let a = Geometry3D::cube(2.0, false);
+        let b = Geometry3D::cube(1.0, false);
+
+        let [u, i, d] = assert_identities(&a, &b);
+        assert_near(u, 8.0);
+        assert_near(i, 1.0);
+        assert_near(d, 7.0);
+    }
+
+    #[test]
+    fn flush_drill() {
+        // The hole goes exactly through, its ends are on the faces of the block
+        let block = Geometry3D::cube(2.0, true);
+        let drill = Geometry3D::cylinder(2.0, 0.5, 0.5, 16, true).unwrap();
+
+        let [u, i, d] = assert_identities(&block, &drill);
+        assert_near(u, 8.0);
+        assert_near(i, volume(&drill));
+        assert_near(d, 8.0 - volume(&drill));
+    }
+
+    #[test]
+    fn empty() {
+        let cube = Geometry3D::cube(1.0, false);
+        let empty = cube.difference(&cube);
+
+        assert!(empty.triangles.is_empty());
+        assert!(empty.union(&empty).triangles.is_empty());
+        assert_near(volume(&empty.union(&cube)), 1.0);
+        assert_near(volume(&cube.difference(&empty)), 1.0);
+    }
+
+    #[test]
+    fn rotated_shapes() {
+        // The axis goes through the middle of two edges of the cube, so the surfaces cross right on them
+        let cube = Geometry3D::cube(2.0, true);
+        let rotated = cube.rotate_axis(V3::new(1.0, 1.0, 0.0), Deg(30.0).into());
+        let cone = Geometry3D::cylinder(3.0, 1.0, 0.0, 12, true).unwrap().translate(V3::new(0.5, 0.0, 0.0));
+        let sphere = Geometry3D::sphere(2).rotate_axis(V3::new(0.0, 1.0, 1.0).normalize(), Deg(45.0).into());
+
+        assert_identities(&cube, &rotated);
+        assert_identities(&cube, &cone);
+        assert_identities(&rotated, &sphere);
+    }
+}
@@ -1,44 +1,160 @@
-use crate::geometry::P3;
+use cgmath::{Angle, Matrix2, Rad, Zero};
 
-use super::{Geometry2D, Geometry3D, FP};
+use crate::geometry::{primitives::Triangles, P3, V3};
+
+use super::{Geometry2D, Geometry3D, EPSILON, FP, V2};
+
+
+/// Parameters of a linear extrusion, like `linear_extrude` in OpenSCAD
+#[derive(Clone, Debug)]
+pub struct LinearExtrusion {
+    pub height: FP,
+    /// Rotation of the top relative to the bottom, clockwise when looking from above
+    pub twist: Rad<FP>,
+    /// Scale of the top relative to the bottom
+    pub scale: V2,
+    /// Number of layers the side walls are split into
+    pub slices: usize,
+    /// Height of the bottom face, `None` puts the middle of the extrusion at z = 0
+    pub bottom: Option<FP>,
+}
+
+impl Default for LinearExtrusion {
+    fn default() -> Self {
+        Self {
+            height: 1.0,
+            twist: Rad::zero(),
+            scale: V2::new(1.0, 1.0),
+            slices: 1,
+            bottom: None,
+        }
+    }
+}
 
 impl Geometry2D {
     pub fn extrude_linear(&self, extent: FP) -> Geometry3D {
-        let extent = extent / 2.0; // Go half down and half up (like `center = true` in OpenSCAD)
+        self.extrude_linear_with(&LinearExtrusion {
+            height: extent,
+            ..Default::default()
+        })
+    }
 
-        /* Setup */
+    pub fn extrude_linear_with(&self, params: &LinearExtrusion) -> Geometry3D {
+        let LinearExtrusion { height, twist, scale, slices, bottom } = *params;
 
-        let vs = self.vertices.len();
-        let mut vertices = vec![];
-        let mut triangles = vec![];
+        assert!(slices >= 1, "Slices ({slices}) should be >= 1");
 
-        /* Algorithm */
+        let bottom = bottom.unwrap_or(- height / 2.0); // Go half down and half up (like `center = true` in OpenSCAD)
+
+        let layers: Vec<Vec<P3>> = (0..=slices).map(|layer| {
+            let t = layer as FP / slices as FP;
+
+            let rot = Matrix2::from_angle(- twist * t);
+            let layer_scale = V2::new(1.0, 1.0) + (scale - V2::new(1.0, 1.0)) * t;
+            let z = bottom + height * t;
 
-        // Add top vertices
-        for v in &self.vertices {
-            vertices.push(P3::new(v.x, v.y, extent));
+            self.vertices.iter().map(|v| {
+                let v = rot * V2::new(v.x * layer_scale.x, v.y * layer_scale.y);
+                P3::new(v.x, v.y, z)
+            }).collect()
+        }).collect();
+
+        let extruded = self.stack_layers(&layers, false);
+
+        if scale.x == 0.0 || scale.y == 0.0 {
+            // The top is collapsed, merge its vertices to drop the degenerate triangles
+            Geometry3D::from(Triangles::new(extruded.iter_triangles().collect()))
+        } else {
+            extruded
         }
-        // Add bottom vertices
-        for v in &self.vertices {
-            vertices.push(P3::new(v.x, v.y, - extent));
+    }
+
+    /// Revolve the shape around the Y axis, which becomes the Z axis.
+    ///
+    /// The shape should not have vertices with negative X.
+    /// Vertices with X of 0 are on the axis and are not duplicated.
+    /// Negative angles revolve the shape clockwise when looking from above.
+    pub fn rotate_extrude(&self, angle: Rad<FP>, segments: usize) -> anyhow::Result<Geometry3D> {
+        anyhow::ensure!(
+            self.vertices.iter().all(|v| v.x > - EPSILON),
+            "Shape should not have vertices with negative X"
+        );
+        anyhow::ensure!(angle != Rad::zero(), "Angle should not be 0");
+
+        if angle < Rad::zero() {
+            // Mirroring flips the triangles, so that they keep facing outwards
+            return Ok(self.rotate_extrude(- angle, segments)?.scale(V3::new(1.0, -1.0, 1.0)));
         }
 
-        // Add top faces
-        for t in &self.triangles {
-            triangles.push(t.clone());
+        let full = angle >= Rad::full_turn();
+
+        anyhow::ensure!(segments >= if full { 3 } else { 1 }, "Segments ({segments}) should be >= 3 for full turns and >= 1 otherwise");
+
+        let angle = if full { Rad::full_turn() } else { angle };
+        let layer_count = if full { segments } else { segments + 1 };
+
+        // Going from the end angle to 0, so that each next layer is in front of the shape
+        let layers: Vec<Vec<P3>> = (0..layer_count).map(|layer| {
+            let a = angle * (1.0 - layer as FP / segments as FP);
+
+            self.vertices.iter().map(|v| {
+                let radius = v.x.max(0.0);
+                P3::new(radius * a.cos(), radius * a.sin(), v.y)
+            }).collect()
+        }).collect();
+
+        let revolved = self.stack_layers(&layers, full);
+
+        if self.vertices.iter().any(|v| v.x < EPSILON) {
+            // Vertices on the axis are the same in all layers, merge them to drop the degenerate triangles
+            Ok(Geometry3D::from(Triangles::new(revolved.iter_triangles().collect())))
+        } else {
+            Ok(revolved)
         }
-        // Add bottom faces
-        for [t0, t1, t2] in &self.triangles {
-            // Note: opposite winding
-            triangles.push([t0 + vs, t2 + vs, t1 + vs]);
+    }
+
+    /// Connect copies of the shape placed in 3D space.
+    ///
+    /// Each layer has a position for each of the vertices of `self`.
+    /// Unless the layers are `closed` into a loop, the first layer gets a bottom face and the last layer gets a top face.
+    pub(super) fn stack_layers(&self, layers: &[Vec<P3>], closed: bool) -> Geometry3D {
+        assert!(layers.len() >= 2, "Layers ({}) should be >= 2", layers.len());
+
+        /* Setup */
+
+        let vs = self.vertices.len();
+        let top = vs * (layers.len() - 1);
+        let vertices: Vec<P3> = layers.iter().flatten().cloned().collect();
+        let mut triangles = vec![];
+
+        /* Algorithm */
+
+        if !closed {
+            // Add bottom faces
+            for [t0, t1, t2] in &self.triangles {
+                // Note: opposite winding
+                triangles.push([*t0, *t2, *t1]);
+            }
+            // Add top faces
+            for [t0, t1, t2] in &self.triangles {
+                triangles.push([t0 + top, t1 + top, t2 + top]);
+            }
         }
 
         // Add side faces
-        for [e0, e1] in self.outer_edge_indices() {
-            // Top triangle
-            triangles.push([e0, e0 + vs, e1]);
-            // Bottom triangle
-            triangles.push([e1 + vs, e1, e0 + vs]);
+        let edges = self.outer_edge_indices();
+        let connections = if closed { layers.len() } else { layers.len() - 1 };
+
+        for layer in 0..connections {
+            let lower = vs * layer;
+            let upper = vs * ((layer + 1) % layers.len());
+
+            for [e0, e1] in &edges {
+                // Top triangle
+                triangles.push([e0 + upper, e0 + lower, e1 + upper]);
+                // Bottom triangle
+                triangles.push([e1 + lower, e1 + upper, e0 + lower]);
+            }
         }
 
         Geometry3D {
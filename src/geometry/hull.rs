@@ -0,0 +1,189 @@
+use std::collections::{HashMap, HashSet};
+
+use super::{
+    predicates::{collinear, orientation, side_of_plane, Orientation, Side},
+    Geometry2D, Geometry3D, P2, P3,
+};
+
+
+/// Corners of the convex hull of the points, counter-clockwise.
+/// Points in the middle of the hull edges are left out.
+fn hull_2d(points: &[P2]) -> Vec<P2> {
+    let mut points = points.to_vec();
+    points.sort_by(|a, b| a.x.total_cmp(&b.x).then(a.y.total_cmp(&b.y)));
+    points.dedup();
+
+    // Andrew's monotone chain: the lower half from left to right, then the upper half back
+    let half = |points: &mut dyn Iterator<Item = &P2>| {
+        let mut chain: Vec<P2> = vec![];
+
+        for p in points {
+            while chain.len() >= 2 && orientation(chain[chain.len() - 2], chain[chain.len() - 1], *p) != Orientation::CounterClockwise {
+                chain.pop();
+            }
+            chain.push(*p);
+        }
+
+        // The last point starts the other half
+        chain.pop();
+        chain
+    };
+
+    let mut hull = half(&mut points.iter());
+    hull.extend(half(&mut points.iter().rev()));
+    hull
+}
+
+
+/// Replace the groups of neighbouring faces that are on the same plane with fans over the corners of their outline.
+/// Points that are inside the faces or in the middle of the edges of the hull are left out.
+fn merge_coplanar(points: &[P3], faces: &[[usize; 3]]) -> Vec<[usize; 3]> {
+    let edges: HashMap<[usize; 2], usize> = faces.iter()
+        .enumerate()
+        .flat_map(|(i, f)| [([f[0], f[1]], i), ([f[1], f[2]], i), ([f[2], f[0]], i)])
+        .collect();
+
+    let mut group = vec![None; faces.len()];
+    let mut merged = vec![];
+
+    for seed in 0..faces.len() {
+        if group[seed].is_some() {
+            continue;
+        }
+
+        let [a, b, c] = faces[seed].map(|i| points[i]);
+
+        // Spread to the neighbours on the plane of the seed face
+        group[seed] = Some(seed);
+        let mut members = vec![seed];
+        let mut stack = vec![seed];
+
+        while let Some(f) = stack.pop() {
+            for [u, v] in [[0, 1], [1, 2], [2, 0]].map(|[i, j]| [faces[f][i], faces[f][j]]) {
+                let neighbour = edges[&[v, u]];
+
+                if group[neighbour].is_none() && faces[neighbour].iter().all(|i| side_of_plane(a, b, c, points[*i]) == Side::On) {
+                    group[neighbour] = Some(seed);
+                    members.push(neighbour);
+                    stack.push(neighbour);
+                }
+            }
+        }
+
+        // The outline goes along the edges that are shared with the other groups
+        let next: HashMap<usize, usize> = members.iter()
+            .flat_map(|f| [[0, 1], [1, 2], [2, 0]].map(|[i, j]| [faces[*f][i], faces[*f][j]]))
+            .filter(|[u, v]| group[edges[&[*v, *u]]] != Some(seed))
+            .map(|[u, v]| (u, v))
+            .collect();
+
+        let start = *next.keys().min().unwrap();
+        let mut outline = vec![start];
+        while next[outline.last().unwrap()] != start {
+            outline.push(next[outline.last().unwrap()]);
+        }
+
+        let n = outline.len();
+        let corners: Vec<usize> = (0..n)
+            .filter(|i| !collinear(points[outline[(i + n - 1) % n]], points[outline[*i]], points[outline[(i + 1) % n]]))
+            .map(|i| outline[i])
+            .collect();
+
+        merged.extend((1..corners.len() - 1).map(|i| [corners[0], corners[i], corners[i + 1]]));
+    }
+
+    merged
+}
+
+
+impl Geometry2D {
+    /// The smallest convex shape that contains all the vertices.
+    pub fn hull(&self) -> anyhow::Result<Self> {
+        let outline = hull_2d(&self.vertices);
+        anyhow::ensure!(outline.len() >= 3, "Hull of points on a single line has no area");
+
+        let triangles = (1..outline.len() - 1).map(|i| [0, i, i + 1]).collect();
+
+        Ok(Self::new(outline, triangles))
+    }
+}
+
+impl Geometry3D {
+    /// The smallest convex solid that contains all the vertices.
+    ///
+    /// Points are added one by one, replacing the faces that they can see with the faces
+    /// going from the edges around them to the point.
+    /// Faces on the same plane are merged at the end, so only the corners of the hull are kept.
+    pub fn hull(&self) -> anyhow::Result<Self> {
+        let mut points = self.vertices.clone();
+        points.sort_by(|a, b| a.x.total_cmp(&b.x).then(a.y.total_cmp(&b.y)).then(a.z.total_cmp(&b.z)));
+        points.dedup();
+
+        // Start with a tetrahedron
+        let not_collinear = (2..points.len()).find(|i| !collinear(points[0], points[1], points[*i]));
+        let Some(c) = not_collinear else {
+            anyhow::bail!("Hull of points on a single line has no volume");
+        };
+
+        let not_coplanar = (2..points.len()).find(|i| side_of_plane(points[0], points[1], points[c], points[*i]) != Side::On);
+        let Some(d) = not_coplanar else {
+            anyhow::bail!("Hull of points on a single plane has no volume");
+        };
+
+        // The faces are counter-clockwise when seen from the outside
+        let (a, b) = match side_of_plane(points[0], points[1], points[c], points[d]) {
+            Side::Below => (0, 1),
+            _ => (1, 0),
+        };
+
+        let mut faces = vec![[a, b, c], [b, a, d], [c, b, d], [a, c, d]];
+
+        for p in 0..points.len() {
+            if [a, b, c, d].contains(&p) {
+                continue;
+            }
+
+            let visible: Vec<bool> = faces.iter()
+                .map(|[u, v, w]| side_of_plane(points[*u], points[*v], points[*w], points[p]) == Side::Above)
+                .collect();
+
+            if !visible.contains(&true) {
+                continue;
+            }
+
+            let edges: HashSet<[usize; 2]> = faces.iter()
+                .zip(&visible)
+                .filter(|(_, visible)| **visible)
+                .flat_map(|(f, _)| [[f[0], f[1]], [f[1], f[2]], [f[2], f[0]]])
+                .collect();
+
+            // Edges between the visible faces and the rest
+            let horizon: Vec<[usize; 2]> = edges.iter()
+                .filter(|[u, v]| !edges.contains(&[*v, *u]))
+                .cloned()
+                .collect();
+
+            let mut kept = visible.iter().map(|visible| !visible);
+            faces.retain(|_| kept.next().unwrap());
+            faces.extend(horizon.into_iter().map(|[u, v]| [u, v, p]));
+        }
+
+        faces = merge_coplanar(&points, &faces);
+
+        // Only keep the points that are corners of the hull
+        let mut used: Vec<Option<usize>> = vec![None; points.len()];
+        let mut vertices = vec![];
+
+        for face in &mut faces {
+            for i in face.iter_mut() {
+                *i = *used[*i].get_or_insert_with(|| {
+                    vertices.push(points[*i]);
+                    vertices.len() - 1
+                });
+            }
+        }
+
+        Ok(Self::new(vertices, faces))
+    }
+}
+
@@ -0,0 +1,109 @@
+use cgmath::{EuclideanSpace, InnerSpace};
+
+use super::{primitives::{Triangle, Triangles}, Geometry2D, Geometry3D, FP, P2, P3};
+
+
+/// Positions along the ring, from 0 at the first point to 1 when coming back to it
+fn arc_positions(ring: &[P2]) -> Vec<FP> {
+    let mut positions = vec![0.0];
+    let mut length = 0.0;
+
+    for (i, p) in ring.iter().enumerate() {
+        length += (ring[(i + 1) % ring.len()] - p).magnitude();
+        positions.push(length);
+    }
+
+    positions.iter().map(|p| p / length).collect()
+}
+
+/// Rotate the ring so that it starts at the point that is the closest to `start`, relative to the centers.
+fn align(ring: &mut [P2], start: P2, start_center: P2) {
+    let center = P2::centroid(ring);
+    let direction = start - start_center;
+
+    let (first, _) = ring.iter()
+        .enumerate()
+        .min_by(|(_, a), (_, b)| {
+            let da = (*a - center - direction).magnitude2();
+            let db = (*b - center - direction).magnitude2();
+            da.total_cmp(&db)
+        })
+        .unwrap();
+
+    ring.rotate_left(first);
+}
+
+
+impl Geometry3D {
+    /// Connect the outlines of the shapes placed at the given heights.
+    ///
+    /// Each shape should be a single outline without holes, the number of vertices can differ.
+    /// The bottom and the top are closed with the first and the last shapes.
+    pub fn loft(profiles: &[Geometry2D], heights: &[FP]) -> anyhow::Result<Self> {
+        anyhow::ensure!(profiles.len() >= 2, "Loft should have at least 2 shapes, got {}", profiles.len());
+        anyhow::ensure!(
+            profiles.len() == heights.len(),
+            "Loft got {} shapes but {} heights", profiles.len(), heights.len()
+        );
+        anyhow::ensure!(heights.windows(2).all(|h| h[0] < h[1]), "Loft heights should be increasing");
+
+        let mut rings = vec![];
+
+        for profile in profiles {
+            let (outlines, holes) = profile.outlines_and_holes();
+            anyhow::ensure!(
+                outlines.len() == 1 && holes.is_empty(),
+                "Loft shapes should be a single outline without holes"
+            );
+            rings.push(outlines.into_iter().next().unwrap().0);
+        }
+
+        for i in 1..rings.len() {
+            let start = rings[i - 1][0];
+            let start_center = P2::centroid(&rings[i - 1]);
+            align(&mut rings[i], start, start_center);
+        }
+
+        let lift = |p: &P2, z: FP| P3::new(p.x, p.y, z);
+        let mut triangles = vec![];
+
+        // Bottom and top faces
+        for t in profiles[0].iter_triangles() {
+            let [t0, t1, t2] = [0, 1, 2].map(|i| lift(&t[i], heights[0]));
+            // Note: opposite winding
+            triangles.push(Triangle::from_points([t0, t2, t1]));
+        }
+        for t in profiles[profiles.len() - 1].iter_triangles() {
+            let z = heights[heights.len() - 1];
+            triangles.push(Triangle::from_points([0, 1, 2].map(|i| lift(&t[i], z))));
+        }
+
+        // Side faces, advancing along the ring that is behind
+        for (layer, pair) in rings.windows(2).enumerate() {
+            let [lower, upper] = [&pair[0], &pair[1]];
+            let (z_lower, z_upper) = (heights[layer], heights[layer + 1]);
+
+            let at_lower = arc_positions(lower);
+            let at_upper = arc_positions(upper);
+
+            let lower_point = |i: usize| lift(&lower[i % lower.len()], z_lower);
+            let upper_point = |j: usize| lift(&upper[j % upper.len()], z_upper);
+
+            let (mut i, mut j) = (0, 0);
+
+            while i < lower.len() || j < upper.len() {
+                let advance_lower = j == upper.len() || (i < lower.len() && at_lower[i + 1] <= at_upper[j + 1]);
+
+                if advance_lower {
+                    triangles.push(Triangle::from_points([lower_point(i), lower_point(i + 1), upper_point(j)]));
+                    i += 1;
+                } else {
+                    triangles.push(Triangle::from_points([lower_point(i), upper_point(j + 1), upper_point(j)]));
+                    j += 1;
+                }
+            }
+        }
+
+        Ok(Self::from(Triangles::new(triangles)))
+    }
+}
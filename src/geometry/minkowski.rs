@@ -0,0 +1,58 @@
+use cgmath::EuclideanSpace;
+
+use super::{
+    predicates::{orientation, Orientation},
+    Geometry2D, Geometry3D, P2,
+};
+
+
+/// Every point of `a` moved by every point of `b`
+fn sums<Point: EuclideanSpace + Copy>(a: &[Point], b: &[Point]) -> Vec<Point> {
+    a.iter().flat_map(|p| b.iter().map(move |q| *p + q.to_vec())).collect()
+}
+
+
+impl Geometry2D {
+    /// Whether the shape is a single convex outline
+    fn is_convex(&self) -> bool {
+        self.outer_edges().iter().all(|[a, b]| {
+            self.vertices.iter().all(|v| orientation(*a, *b, *v) != Orientation::Clockwise)
+        })
+    }
+
+    /// Corners of the convex parts of the shape, either the whole shape or each of its triangles
+    fn convex_parts(&self) -> Vec<Vec<P2>> {
+        if self.is_convex() {
+            vec![self.vertices.clone()]
+        } else {
+            self.triangles.iter().map(|t| t.map(|i| self.vertices[i]).to_vec()).collect()
+        }
+    }
+
+    /// Minkowski sum, which is the shape covered by `other` when moving it over all the points of this shape.
+    ///
+    /// Sums of convex parts are the hulls of the sums of their corners,
+    /// so concave shapes are split into triangles and the sums of those are put together.
+    pub fn minkowski(&self, other: &Self) -> anyhow::Result<Self> {
+        let other_parts = other.convex_parts();
+
+        let mut parts = self.convex_parts()
+            .into_iter()
+            .flat_map(|a| other_parts.iter().map(move |b| Self::new(sums(&a, b), vec![]).hull()))
+            .filter_map(|part| part.ok());
+
+        let Some(first) = parts.next() else {
+            anyhow::bail!("Minkowski sum has no area");
+        };
+
+        Ok(parts.fold(first, |sum, part| sum.union(&part)))
+    }
+}
+
+impl Geometry3D {
+    /// Minkowski sum of convex geometries, which is the hull of the sums of their vertices.
+    /// Concave geometries are replaced with their hulls.
+    pub fn minkowski(&self, other: &Self) -> anyhow::Result<Self> {
+        Self::new(sums(&self.vertices, &other.vertices), vec![]).hull()
+    }
+}
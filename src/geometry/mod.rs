@@ -1,8 +1,16 @@
 pub mod primitives;
 pub mod boolean;
+pub mod bvh;
+pub mod csg;
 pub mod extrude;
-
-use cgmath::{AbsDiffEq, ElementWise, EuclideanSpace, Matrix2, Rad};
+pub mod hull;
+pub mod loft;
+pub mod minkowski;
+pub mod offset;
+pub mod predicates;
+pub mod sweep;
+
+use cgmath::{AbsDiffEq, Angle, Array, Basis3, ElementWise, EuclideanSpace, InnerSpace, Matrix2, Rad, Rotation, Rotation3, SquareMatrix, Transform};
 use primitives::*;
 
 
@@ -20,6 +28,15 @@ impl<Point: Clone + std::fmt::Debug> IndexedTriangles<Point> {
         }
     }
 
+    pub fn vertices(&self) -> &[Point] {
+        &self.vertices
+    }
+
+    /// Indices of the vertices of each triangle
+    pub fn triangles(&self) -> &[[usize; 3]] {
+        &self.triangles
+    }
+
     pub fn iter_vertices(&self) -> std::vec::IntoIter<Point> {
         let mut vs = vec![];
 
@@ -47,7 +64,7 @@ impl<Point: Clone + std::fmt::Debug> IndexedTriangles<Point> {
     }
 
     /// Returns edges that are used by an odd number of triangles.
-    /// For 2D objects, this is their outlines and holes.
+    /// For 2D objects, this is their outlines and holes (see [`Geometry2D::rings`]).
     /// For 3D objects, this is what would make them non-manifold.
     pub fn outer_edge_indices(&self) -> Vec<[usize; 2]> {
         use std::collections::BTreeSet;
@@ -65,8 +82,6 @@ impl<Point: Clone + std::fmt::Debug> IndexedTriangles<Point> {
             }
         }
 
-        // TODO: differentiate the outline and holes
-
         edges.into_iter().collect()
     }
 
@@ -93,6 +108,14 @@ impl<Point: Clone + std::fmt::Debug> IndexedTriangles<Point> {
         }
     }
 
+    /// Reverse the winding of the triangles, which turns them the other way around.
+    pub fn flip(&self) -> Self {
+        Self {
+            vertices: self.vertices.clone(),
+            triangles: self.triangles.iter().map(|[t0, t1, t2]| [*t0, *t2, *t1]).collect(),
+        }
+    }
+
     pub fn translate(&self, vector: Point::Diff) -> Self
     where
         Point: EuclideanSpace
@@ -107,26 +130,138 @@ impl<Point: Clone + std::fmt::Debug> IndexedTriangles<Point> {
         }
     }
 
+    /// Move every vertex with `f`.
+    /// When the transformation `reverses` the orientation (like mirroring does),
+    /// the triangles are flipped, so that they keep facing outwards.
+    fn map_vertices<F: Fn(&Point) -> Point>(&self, f: F, reverses: bool) -> Self {
+        let transformed = Self {
+            vertices: self.vertices.iter().map(f).collect(),
+            triangles: self.triangles.clone(),
+        };
+
+        if reverses {
+            transformed.flip()
+        } else {
+            transformed
+        }
+    }
+
     pub fn scale(&self, vector: Point::Diff) -> Self
     where
-        Point: EuclideanSpace + ElementWise
+        Point: EuclideanSpace<Scalar = FP> + ElementWise
     {
-        // FIXME: with negative scalings, triangle winding needs to be corrected
-
         let elwise = Point::from_vec(vector);
-        let vertices = self.vertices.iter().map(|vertex| {
-            vertex.mul_element_wise(elwise)
-        }).collect();
 
-        Self {
-            vertices,
-            triangles: self.triangles.clone(),
-        }
+        // An odd number of negative scalings mirrors the geometry
+        let reverses = elwise.product() < 0.0;
+
+        self.map_vertices(|vertex| vertex.mul_element_wise(elwise), reverses)
+    }
+
+    /// Apply an affine transformation, given as a 3x3 matrix for 2D or a 4x4 matrix for 3D.
+    /// The last row of the matrix should be `(0, .., 0, 1)`, otherwise the points get divided by their `w` coordinate.
+    pub fn transform<M>(&self, matrix: M) -> Self
+    where
+        Point: EuclideanSpace<Scalar = FP>,
+        M: Transform<Point> + SquareMatrix<Scalar = FP>
+    {
+        // A negative determinant means the transformation includes a reflection
+        let reverses = matrix.determinant() < 0.0;
+
+        self.map_vertices(|vertex| matrix.transform_point(*vertex), reverses)
     }
 }
 
 impl IndexedTriangles<P2> {
+    /// Chains the outer edges into closed rings.
+    /// Outlines go counter-clockwise and holes go clockwise.
+    pub fn rings(&self) -> Vec<Outline2D> {
+        use std::collections::BTreeMap;
+
+        let mut outgoing: BTreeMap<usize, Vec<usize>> = BTreeMap::new();
+
+        for [a, b] in self.outer_edge_indices() {
+            outgoing.entry(a).or_default().push(b);
+        }
+
+        let take_next = |outgoing: &mut BTreeMap<usize, Vec<usize>>, prev: usize, current: usize| {
+            let candidates = outgoing.get_mut(&current)?;
+
+            // When rings touch at a vertex, keep the region on the left by taking
+            // the first edge clockwise from the one we came from
+            let back = self.vertices[prev] - self.vertices[current];
+            let clockwise_angle = |to: &usize| {
+                let dir = self.vertices[*to] - self.vertices[current];
+                let angle = - back.perp_dot(dir).atan2(back.dot(dir));
+                if angle <= 0.0 { angle + std::f64::consts::TAU } else { angle }
+            };
+
+            let (i, _) = candidates.iter()
+                .enumerate()
+                .min_by(|(_, a), (_, b)| clockwise_angle(a).total_cmp(&clockwise_angle(b)))?;
+            let next = candidates.swap_remove(i);
+
+            if candidates.is_empty() {
+                outgoing.remove(&current);
+            }
+
+            Some(next)
+        };
+
+        let mut rings = vec![];
+
+        while let Some((&start, targets)) = outgoing.iter_mut().next() {
+            let mut ring = vec![self.vertices[start]];
+            let mut prev = start;
+            let mut current = targets.pop().unwrap();
 
+            if targets.is_empty() {
+                outgoing.remove(&start);
+            }
+
+            while current != start {
+                ring.push(self.vertices[current]);
+
+                let Some(next) = take_next(&mut outgoing, prev, current) else {
+                    break;
+                };
+
+                prev = current;
+                current = next;
+            }
+
+            rings.push(Outline2D(ring));
+        }
+
+        rings
+    }
+
+    /// Rings split into counter-clockwise outlines and clockwise holes.
+    pub fn outlines_and_holes(&self) -> (Vec<Outline2D>, Vec<Outline2D>) {
+        self.rings().into_iter().partition(Outline2D::is_ccw)
+    }
+
+    /// Outlines with the holes that are inside of them.
+    pub fn polygons(&self) -> Vec<Polygon2D> {
+        let (mut outlines, holes) = self.outlines_and_holes();
+
+        // Smaller outlines go first, so that holes end up in the innermost outline containing them
+        outlines.sort_by(|a, b| a.signed_area().total_cmp(&b.signed_area()));
+
+        let mut polygons: Vec<_> = outlines.into_iter()
+            .map(|outline| Polygon2D::new(outline, vec![]))
+            .collect();
+
+        for hole in holes {
+            let probe = P2::midpoint(hole.0[0], hole.0[1]);
+
+            if let Some(polygon) = polygons.iter_mut().find(|p| p.outline.contains(probe)) {
+                polygon.holes.push(hole);
+            }
+        }
+
+        polygons
+    }
 }
 
 impl IndexedTriangles<P3> {
@@ -151,31 +286,17 @@ impl IndexedTriangles<P3> {
 
 impl<Point> From<Triangles<Point>> for IndexedTriangles<Point>
 where
-    Point: AbsDiffEq<Point, Epsilon = FP> + Clone + std::fmt::Debug
+    Point: AbsDiffEq<Point, Epsilon = FP> + Array<Element = FP> + Clone + std::fmt::Debug
 {
     fn from(value: Triangles<Point>) -> Self {
-        let mut vertices = vec![];
-        let mut triangles = vec![];
-
-        let find_vertex = |vs: &Vec<Point>, v: &Point| vs.iter()
-            .position(|gv: &Point|
-                cgmath::abs_diff_eq!(v, gv, epsilon = EPSILON)
-            );
-
-        for triangle in value.into_iter() {
-            let mut global_indices = [0usize; 3];
-
-            for (vi, v) in triangle.into_iter().enumerate() {
-                if let Some(global_index) = find_vertex(&vertices, &v) {
-                    global_indices[vi] = global_index;
-                } else {
-                    vertices.push(v);
-                    global_indices[vi] = vertices.len() - 1;
-                }
-            }
+        let points: Vec<Point> = value.into_iter().flatten().collect();
+        let (vertices, indices) = weld(&points);
 
-            triangles.push(global_indices);
-        }
+        let triangles = indices.chunks(3)
+            .map(|t| [t[0], t[1], t[2]])
+            // Triangles that collapsed after merging the vertices are not triangles anymore
+            .filter(|[t0, t1, t2]| t0 != t1 && t1 != t2 && t2 != t0)
+            .collect();
 
         Self {
             vertices,
@@ -184,6 +305,62 @@ where
     }
 }
 
+/// Merge the points that are closer than `EPSILON` to each other, along each of the axes.
+/// Returns the merged points, and for each of the given points the index of its merged point.
+///
+/// The merged points are put into a grid, so only the ones in the neighbouring cells are compared.
+fn weld<Point>(points: &[Point]) -> (Vec<Point>, Vec<usize>)
+where
+    Point: AbsDiffEq<Point, Epsilon = FP> + Array<Element = FP> + Clone
+{
+    use std::collections::HashMap;
+
+    // Cells are larger than `EPSILON`, so that close points are at most one cell apart even after rounding
+    let size = 2.0 * EPSILON;
+    let dimensions = Point::len();
+
+    let cell = |p: &Point| {
+        let mut cell = [0i64; 3];
+        for (k, c) in cell.iter_mut().enumerate().take(dimensions) {
+            *c = (p[k] / size).floor() as i64;
+        }
+        cell
+    };
+
+    let neighbours = |cell: [i64; 3]| (0..3usize.pow(dimensions as u32)).map(move |mut i| {
+        let mut neighbour = cell;
+        for c in neighbour.iter_mut().take(dimensions) {
+            *c += (i % 3) as i64 - 1;
+            i /= 3;
+        }
+        neighbour
+    });
+
+    let mut grid: HashMap<[i64; 3], Vec<usize>> = HashMap::new();
+    let mut merged: Vec<Point> = vec![];
+    let mut indices = Vec::with_capacity(points.len());
+
+    for point in points {
+        let cell = cell(point);
+
+        // The first one that was merged, like when going through all of them
+        let found = neighbours(cell)
+            .filter_map(|n| grid.get(&n))
+            .flatten()
+            .filter(|m| cgmath::abs_diff_eq!(point, &merged[**m], epsilon = EPSILON))
+            .min();
+
+        if let Some(index) = found {
+            indices.push(*index);
+        } else {
+            merged.push(point.clone());
+            indices.push(merged.len() - 1);
+            grid.entry(cell).or_default().push(merged.len() - 1);
+        }
+    }
+
+    (merged, indices)
+}
 
 /* =========== *
  * 2D geometry *
@@ -223,6 +400,73 @@ impl Geometry2D {
 
         Self::try_from(outline).unwrap()
     }
+
+    /// Rectangle with the given size, either centered or with a corner at the origin.
+    pub fn rect(width: FP, height: FP, centered: bool) -> anyhow::Result<Self> {
+        anyhow::ensure!(width > 0.0 && height > 0.0, "Size ({width}, {height}) should be > 0");
+
+        let origin = if centered { P2::new(- width / 2.0, - height / 2.0) } else { P2::new(0.0, 0.0) };
+
+        let outline = Outline2D(vec![
+            origin,
+            origin + V2::new(width, 0.0),
+            origin + V2::new(width, height),
+            origin + V2::new(0.0, height),
+        ]);
+
+        Self::try_from(outline)
+    }
+
+    /// Centered rectangle with corners rounded with the given radius.
+    /// Each corner is split into `segments` segments.
+    pub fn rounded_rect(width: FP, height: FP, radius: FP, segments: usize) -> anyhow::Result<Self> {
+        use cgmath::{Basis2, Rotation, Rotation2};
+
+        anyhow::ensure!(width > 0.0 && height > 0.0, "Size ({width}, {height}) should be > 0");
+        anyhow::ensure!(
+            0.0 <= radius && radius <= width.min(height) / 2.0,
+            "Radius ({radius}) should be >= 0 and <= half of the smaller side"
+        );
+        anyhow::ensure!(segments >= 1, "Segments ({segments}) should be >= 1");
+
+        let inner = V2::new(width / 2.0 - radius, height / 2.0 - radius);
+        let rot: Basis2<FP> = Rotation2::from_angle(Rad::turn_div_4() / segments as FP);
+
+        let mut outline = Outline2D(vec![]);
+
+        // Corner centers, counter-clockwise from the top right, each one with its quarter of a circle
+        let corners = [(1.0, 1.0), (-1.0, 1.0), (-1.0, -1.0), (1.0, -1.0)];
+
+        for (quarter, (sx, sy)) in corners.into_iter().enumerate() {
+            let center = P2::new(sx * inner.x, sy * inner.y);
+            let start: Basis2<FP> = Rotation2::from_angle(Rad::turn_div_4() * quarter as FP);
+            let mut v = start.rotate_vector(V2::new(radius, 0.0));
+
+            for _ in 0..=segments {
+                outline.0.push(center + v);
+                v = rot.rotate_vector(v);
+            }
+        }
+
+        outline.0.dedup_by(|a, b| cgmath::abs_diff_eq!(a, b, epsilon = EPSILON));
+        if outline.0.len() > 1 && cgmath::abs_diff_eq!(outline.0[0], outline.0[outline.0.len() - 1], epsilon = EPSILON) {
+            outline.0.pop();
+        }
+
+        Self::try_from(outline)
+    }
+
+    pub fn ellipse(radius_x: FP, radius_y: FP, segments: usize) -> anyhow::Result<Self> {
+        anyhow::ensure!(radius_x > 0.0 && radius_y > 0.0, "Radiuses ({radius_x}, {radius_y}) should be > 0");
+        anyhow::ensure!(segments >= 3, "Segments ({segments}) should be >= 3");
+
+        Ok(Self::circle(segments).scale(V2::new(radius_x, radius_y)))
+    }
+
+    /// Shape with the given outline, which can be concave but should not intersect itself.
+    pub fn polygon(points: Vec<P2>) -> anyhow::Result<Self> {
+        Self::try_from(Outline2D(points))
+    }
 }
 
 impl TryFrom<Outline2D> for Geometry2D {
@@ -233,6 +477,14 @@ impl TryFrom<Outline2D> for Geometry2D {
     }
 }
 
+impl TryFrom<Polygon2D> for Geometry2D {
+    type Error = anyhow::Error;
+
+    fn try_from(value: Polygon2D) -> Result<Self, Self::Error> {
+        Ok(Self::from(Triangles::<P2>::try_from(value)?))
+    }
+}
+
 
 /* =========== *
  * 3D geometry *
@@ -241,6 +493,34 @@ impl TryFrom<Outline2D> for Geometry2D {
 pub type Geometry3D = IndexedTriangles<P3>;
 
 impl Geometry3D {
+    pub fn rotate(&self, rotation: Basis3<FP>) -> Self {
+        let vertices = self.vertices.iter().map(|vertex| {
+            rotation.rotate_point(*vertex)
+        }).collect();
+
+        Self {
+            vertices,
+            triangles: self.triangles.clone(),
+        }
+    }
+
+    /// Rotate around X, then around Y, then around Z (like `rotate([x, y, z])` in OpenSCAD).
+    pub fn rotate_euler(&self, x: Rad<FP>, y: Rad<FP>, z: Rad<FP>) -> Self {
+        self.rotate(Basis3::from_angle_z(z) * Basis3::from_angle_y(y) * Basis3::from_angle_x(x))
+    }
+
+    /// Rotate counter-clockwise around the axis, when looking from where it points.
+    pub fn rotate_axis(&self, axis: V3, angle: Rad<FP>) -> Self {
+        self.rotate(Basis3::from_axis_angle(axis.normalize(), angle))
+    }
+
+    /// Reflect across the plane that goes through the origin and has the given normal.
+    pub fn mirror(&self, normal: V3) -> Self {
+        let normal = normal.normalize();
+
+        self.map_vertices(|vertex| vertex - normal * 2.0 * vertex.to_vec().dot(normal), true)
+    }
+
     pub fn sphere(subdivisions: usize) -> Self {
         let subdivided = hexasphere::shapes::IcoSphere::new(subdivisions, |_| ());
 
@@ -261,4 +541,73 @@ impl Geometry3D {
             triangles,
         }
     }
+
+    /// Box with the given size, either centered or with a corner at the origin.
+    pub fn cuboid(size: V3, centered: bool) -> Self {
+        let offset = if centered { - size / 2.0 } else { V3::new(0.0, 0.0, 0.0) };
+
+        // Bits of the index say whether the vertex is at the far side of X, Y and Z
+        let vertices = (0..8).map(|i| {
+            P3::new(
+                (i & 1) as FP * size.x,
+                (i >> 1 & 1) as FP * size.y,
+                (i >> 2 & 1) as FP * size.z,
+            ) + offset
+        }).collect();
+
+        let triangles = vec![
+            [0, 2, 3], [0, 3, 1], // -Z
+            [4, 5, 7], [4, 7, 6], // +Z
+            [0, 1, 5], [0, 5, 4], // -Y
+            [2, 6, 7], [2, 7, 3], // +Y
+            [0, 4, 6], [0, 6, 2], // -X
+            [1, 3, 7], [1, 7, 5], // +X
+        ];
+
+        Self {
+            vertices,
+            triangles,
+        }
+    }
+
+    pub fn cube(size: FP, centered: bool) -> Self {
+        Self::cuboid(V3::new(size, size, size), centered)
+    }
+
+    /// Cylinder around the Z axis, or a cone if one of the radiuses is 0.
+    /// Either centered or with the bottom at z = 0.
+    pub fn cylinder(height: FP, radius_bottom: FP, radius_top: FP, segments: usize, centered: bool) -> anyhow::Result<Self> {
+        anyhow::ensure!(segments >= 3, "Segments ({segments}) should be >= 3");
+        anyhow::ensure!(height > 0.0, "Height ({height}) should be > 0");
+        anyhow::ensure!(radius_bottom >= 0.0 && radius_top >= 0.0, "Radiuses ({radius_bottom}, {radius_top}) should be >= 0");
+        anyhow::ensure!(radius_bottom > 0.0 || radius_top > 0.0, "At least one of the radiuses should be > 0");
+
+        let bottom = if centered { - height / 2.0 } else { 0.0 };
+        let top = bottom + height;
+
+        let mut profile = vec![P2::new(0.0, bottom)];
+        if radius_bottom > 0.0 {
+            profile.push(P2::new(radius_bottom, bottom));
+        }
+        if radius_top > 0.0 {
+            profile.push(P2::new(radius_top, top));
+        }
+        profile.push(P2::new(0.0, top));
+
+        Geometry2D::try_from(Outline2D(profile))?.rotate_extrude(Rad::full_turn(), segments)
+    }
+
+    /// Ring around the Z axis, centered at the origin.
+    pub fn torus(major_radius: FP, minor_radius: FP, major_segments: usize, minor_segments: usize) -> anyhow::Result<Self> {
+        anyhow::ensure!(
+            0.0 < minor_radius && minor_radius < major_radius,
+            "Minor radius ({minor_radius}) should be > 0 and < major radius ({major_radius})"
+        );
+        anyhow::ensure!(minor_segments >= 3, "Minor segments ({minor_segments}) should be >= 3");
+
+        Geometry2D::circle(minor_segments)
+            .scale(V2::new(minor_radius, minor_radius))
+            .translate(V2::new(major_radius, 0.0))
+            .rotate_extrude(Rad::full_turn(), major_segments)
+    }
 }
@@ -0,0 +1,140 @@
+use cgmath::{Basis2, InnerSpace, Rad, Rotation, Rotation2};
+
+use super::{
+    predicates::{orientation, Orientation},
+    primitives::Outline2D,
+    Geometry2D, EPSILON, FP, P2, V2,
+};
+
+
+/// How the offset edges are connected around the corners
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Join {
+    /// Arcs, with the number of segments that a full circle would have
+    Round(usize),
+    /// Sharp corners, cut off when they go further from the corner than the limit times the offset.
+    /// Limits below 1 count as 1.
+    Miter(FP),
+    /// Corners cut off at the offset distance
+    Square,
+}
+
+impl Default for Join {
+    fn default() -> Self {
+        Join::Round(32)
+    }
+}
+
+/// Band along the edge, going from it by `shift`
+fn strip(a: P2, b: P2, shift: V2) -> Option<Geometry2D> {
+    Geometry2D::polygon(vec![a, b, b + shift, a + shift]).ok()
+}
+
+/// Shape that fills the gap around the corner `v` between the bands of its edges,
+/// which are shifted by `from` and `to`, and go in the directions `incoming` and `outgoing`.
+fn join(v: P2, from: V2, to: V2, incoming: V2, outgoing: V2, join: Join) -> Option<Geometry2D> {
+    let distance = from.magnitude();
+
+    // Direction halfway between the bands, going along the edges when they turn back
+    let bisector = if (from + to).magnitude() < EPSILON * distance {
+        incoming
+    } else {
+        (from + to).normalize()
+    };
+
+    let mut outline = vec![v, v + from];
+
+    match join {
+        Join::Round(segments) => {
+            // Going around the outside of the corner, which is counter-clockwise for the outwards offset
+            let angle = from.angle(to).0.abs();
+            let steps = ((angle / std::f64::consts::TAU * segments as FP).ceil() as usize).max(1);
+            let rot: Basis2<FP> = Rotation2::from_angle(Rad(angle.copysign(from.perp_dot(incoming)) / steps as FP));
+
+            let mut u = from;
+            for _ in 1..steps {
+                u = rot.rotate_vector(u);
+                outline.push(v + u);
+            }
+        }
+        Join::Miter(_) | Join::Square => {
+            let limit = match join {
+                // Below 1 the cut would go inside the corner of the bands
+                Join::Miter(limit) => limit.max(1.0) * distance,
+                _ => distance,
+            };
+
+            // How far the corner of the extended bands is
+            let miter = distance * distance / from.dot(bisector);
+
+            if miter <= limit {
+                outline.push(v + bisector * miter);
+            } else {
+                // Where the extended bands cross the line going across the bisector at the limit
+                let along_in = (limit - from.dot(bisector)) / incoming.dot(bisector);
+                let along_out = (limit - to.dot(bisector)) / -outgoing.dot(bisector);
+
+                outline.push(v + from + incoming * along_in);
+                outline.push(v + to - outgoing * along_out);
+            }
+        }
+    }
+
+    outline.push(v + to);
+
+    Geometry2D::polygon(outline).ok()
+}
+
+
+impl Geometry2D {
+    /// Move the outlines and the holes outwards by `delta`, or inwards when it's negative.
+    ///
+    /// Insets can split the shape into several parts, or make it disappear altogether.
+    pub fn offset(&self, delta: FP, join_type: Join) -> Self {
+        if delta == 0.0 {
+            return self.clone();
+        }
+
+        let mut band = Self::new(vec![], vec![]);
+
+        // The inside is on the left of each ring
+        for Outline2D(ring) in self.rings() {
+            let n = ring.len();
+
+            for i in 0..n {
+                let (u, v, w) = (ring[(i + n - 1) % n], ring[i], ring[(i + 1) % n]);
+
+                let incoming = (v - u).normalize();
+                let outgoing = (w - v).normalize();
+
+                // To the right of the edges is the outside
+                let from = V2::new(incoming.y, -incoming.x) * delta;
+                let to = V2::new(outgoing.y, -outgoing.x) * delta;
+
+                if let Some(strip) = strip(v, w, to) {
+                    band = band.concat(&strip);
+                }
+
+                // Bands of the edges overlap at the corners that turn away from the offset,
+                // and leave a gap at the ones that turn towards it or go back
+                let turn = if delta > 0.0 { Orientation::CounterClockwise } else { Orientation::Clockwise };
+                let gap = match orientation(u, v, w) {
+                    Orientation::Collinear => incoming.dot(outgoing) < 0.0,
+                    o => o == turn,
+                };
+
+                if gap {
+                    if let Some(join) = join(v, from, to, incoming, outgoing, join_type) {
+                        band = band.concat(&join);
+                    }
+                }
+            }
+        }
+
+        if delta > 0.0 {
+            self.union(&band)
+        } else {
+            self.difference(&band)
+        }
+    }
+}
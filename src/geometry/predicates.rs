@@ -0,0 +1,203 @@
+use super::{P2, P3, FP};
+
+
+/// Order in which three points go around
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Orientation {
+    Clockwise,
+    Collinear,
+    CounterClockwise,
+}
+
+/// Exact orientation of the points `a`, `b` and `c`.
+/// Counter-clockwise means that `c` is to the left of the line going from `a` to `b`.
+pub fn orientation(a: P2, b: P2, c: P2) -> Orientation {
+    let coord = |p: P2| robust::Coord { x: p.x, y: p.y };
+
+    let det = robust::orient2d(coord(a), coord(b), coord(c));
+
+    if det > 0.0 {
+        Orientation::CounterClockwise
+    } else if det < 0.0 {
+        Orientation::Clockwise
+    } else {
+        Orientation::Collinear
+    }
+}
+
+/// Whether the points `a`, `b` and `c` are on one line, exactly
+pub fn collinear(a: P3, b: P3, c: P3) -> bool {
+    [[0, 1], [1, 2], [2, 0]].iter().all(|[u, v]| {
+        let [a, b, c] = [a, b, c].map(|p| P2::new(p[*u], p[*v]));
+        orientation(a, b, c) == Orientation::Collinear
+    })
+}
+
+
+/// Where a point is relative to a plane
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Side {
+    Below,
+    On,
+    Above,
+}
+
+/// Exact side of the plane going through `a`, `b` and `c` that the point `p` is on.
+/// Above is where `a`, `b` and `c` are seen going counter-clockwise.
+pub fn side_of_plane(a: P3, b: P3, c: P3, p: P3) -> Side {
+    let coord = |p: P3| robust::Coord3D { x: p.x, y: p.y, z: p.z };
+
+    // Positive when below
+    let det = robust::orient3d(coord(a), coord(b), coord(c), coord(p));
+
+    if det < 0.0 {
+        Side::Above
+    } else if det > 0.0 {
+        Side::Below
+    } else {
+        Side::On
+    }
+}
+
+/// How 2 line segments intersect
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SegmentIntersection {
+    /// The segments have nothing in common
+    Disjoint,
+    /// The segments cross at a point that is inside of both of them
+    Crossing(P2),
+    /// The segments meet at an endpoint of at least one of them
+    Touching(P2),
+    /// The segments are collinear and share a part between these endpoints
+    Overlapping([P2; 2]),
+}
+
+/// Classify the intersection of segments `a` and `b`.
+///
+/// Only `Crossing` produces a new point, which is rounded.
+/// Everything else is decided exactly and returns the original endpoints.
+pub fn segment_intersection(a: &[P2; 2], b: &[P2; 2]) -> SegmentIntersection {
+    use Orientation::*;
+
+    let a0_b = orientation(b[0], b[1], a[0]);
+    let a1_b = orientation(b[0], b[1], a[1]);
+    let b0_a = orientation(a[0], a[1], b[0]);
+    let b1_a = orientation(a[0], a[1], b[1]);
+
+    if b0_a == Collinear && b1_a == Collinear {
+        return collinear_intersection(a, b);
+    }
+
+    if a0_b == a1_b || b0_a == b1_a {
+        return SegmentIntersection::Disjoint;
+    }
+
+    // The segments intersect at a single point, check whether it's one of the endpoints
+    for (o, p) in [(a0_b, a[0]), (a1_b, a[1]), (b0_a, b[0]), (b1_a, b[1])] {
+        if o == Collinear {
+            return SegmentIntersection::Touching(p);
+        }
+    }
+
+    let da = a[1] - a[0];
+    let db = b[1] - b[0];
+
+    // Position of the intersection along `a`, from 0 to 1
+    let t = (b[0] - a[0]).perp_dot(db) / da.perp_dot(db);
+    let point = a[0] + da * t.clamp(0.0, 1.0);
+
+    SegmentIntersection::Crossing(point)
+}
+
+fn collinear_intersection(a: &[P2; 2], b: &[P2; 2]) -> SegmentIntersection {
+    // Compare the points along the axis where the segment `a` is the longest
+    let along = |p: &P2| -> FP {
+        let d = a[1] - a[0];
+        if d.x.abs() >= d.y.abs() { p.x } else { p.y }
+    };
+
+    let sorted = |s: &[P2; 2]| if along(&s[0]) <= along(&s[1]) { [s[0], s[1]] } else { [s[1], s[0]] };
+    let [a_min, a_max] = sorted(a);
+    let [b_min, b_max] = sorted(b);
+
+    let start = if along(&a_min) >= along(&b_min) { a_min } else { b_min };
+    let end = if along(&a_max) <= along(&b_max) { a_max } else { b_max };
+
+    match along(&start).total_cmp(&along(&end)) {
+        std::cmp::Ordering::Greater => SegmentIntersection::Disjoint,
+        std::cmp::Ordering::Equal => SegmentIntersection::Touching(start),
+        std::cmp::Ordering::Less => SegmentIntersection::Overlapping([start, end]),
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn segment(a: [FP; 2], b: [FP; 2]) -> [P2; 2] {
+        [a.into(), b.into()]
+    }
+
+    #[test]
+    fn crossing() {
+        let vertical = segment([1.0, 0.0], [1.0, 2.0]);
+        let horizontal = segment([0.0, 1.0], [2.0, 1.0]);
+
+        assert_eq!(segment_intersection(&vertical, &horizontal), SegmentIntersection::Crossing(P2::new(1.0, 1.0)));
+        assert_eq!(segment_intersection(&horizontal, &vertical), SegmentIntersection::Crossing(P2::new(1.0, 1.0)));
+    }
+
+    #[test]
+    fn touching_at_an_endpoint() {
+        // Sharing an endpoint
+        let a = segment([0.0, 0.0], [1.0, 1.0]);
+        let b = segment([1.0, 1.0], [2.0, 0.0]);
+        assert_eq!(segment_intersection(&a, &b), SegmentIntersection::Touching(P2::new(1.0, 1.0)));
+
+        // Ending in the middle of the other one
+        let vertical = segment([1.0, 0.0], [1.0, 1.0]);
+        let horizontal = segment([0.0, 0.0], [2.0, 0.0]);
+        assert_eq!(segment_intersection(&vertical, &horizontal), SegmentIntersection::Touching(P2::new(1.0, 0.0)));
+        assert_eq!(segment_intersection(&horizontal, &vertical), SegmentIntersection::Touching(P2::new(1.0, 0.0)));
+    }
+
+    #[test]
+    fn collinear_overlap() {
+        let a = segment([0.0, 0.0], [0.0, 2.0]);
+        let b = segment([0.0, 3.0], [0.0, 1.0]);
+        assert_eq!(segment_intersection(&a, &b), SegmentIntersection::Overlapping([P2::new(0.0, 1.0), P2::new(0.0, 2.0)]));
+
+        // One inside the other
+        let a = segment([4.0, 2.0], [0.0, 0.0]);
+        let b = segment([1.0, 0.5], [3.0, 1.5]);
+        assert_eq!(segment_intersection(&a, &b), SegmentIntersection::Overlapping([P2::new(1.0, 0.5), P2::new(3.0, 1.5)]));
+    }
+
+    #[test]
+    fn collinear_touching() {
+        let a = segment([0.0, 0.0], [1.0, 0.0]);
+        let b = segment([2.0, 0.0], [1.0, 0.0]);
+        assert_eq!(segment_intersection(&a, &b), SegmentIntersection::Touching(P2::new(1.0, 0.0)));
+
+        let a = segment([0.0, 0.0], [0.0, 1.0]);
+        let b = segment([0.0, 1.0], [0.0, 2.0]);
+        assert_eq!(segment_intersection(&a, &b), SegmentIntersection::Touching(P2::new(0.0, 1.0)));
+    }
+
+    #[test]
+    fn disjoint() {
+        // Collinear with a gap
+        let a = segment([0.0, 0.0], [0.0, 1.0]);
+        let b = segment([0.0, 2.0], [0.0, 3.0]);
+        assert_eq!(segment_intersection(&a, &b), SegmentIntersection::Disjoint);
+
+        // Parallel
+        let b = segment([1.0, 0.0], [1.0, 1.0]);
+        assert_eq!(segment_intersection(&a, &b), SegmentIntersection::Disjoint);
+
+        // Would cross if they were longer
+        let b = segment([1.0, 0.5], [3.0, 0.5]);
+        assert_eq!(segment_intersection(&a, &b), SegmentIntersection::Disjoint);
+    }
+}
@@ -108,25 +108,143 @@ impl<Point: Clone + std::fmt::Debug> Triangles<Point> {
 #[derive(Clone, Debug)]
 pub struct Outline2D(pub Vec<P2>);
 
+impl Outline2D {
+    /// Positive for counter-clockwise outlines, negative for clockwise ones.
+    pub fn signed_area(&self) -> FP {
+        let mut area = 0.0;
+
+        for (i, a) in self.0.iter().enumerate() {
+            let b = self.0[(i + 1) % self.0.len()];
+            area += a.x * b.y - b.x * a.y;
+        }
+
+        area / 2.0
+    }
+
+    pub fn is_ccw(&self) -> bool {
+        self.signed_area() > 0.0
+    }
+
+    pub fn reversed(&self) -> Self {
+        Self(self.0.iter().rev().cloned().collect())
+    }
+
+    /// Whether the point is inside the outline, using the even-odd rule.
+    pub fn contains(&self, point: P2) -> bool {
+        let mut inside = false;
+
+        for (i, a) in self.0.iter().enumerate() {
+            let b = self.0[(i + 1) % self.0.len()];
+
+            if (a.y > point.y) != (b.y > point.y) {
+                let x = a.x + (point.y - a.y) / (b.y - a.y) * (b.x - a.x);
+                if point.x < x {
+                    inside = !inside;
+                }
+            }
+        }
+
+        inside
+    }
+}
+
 impl TryFrom<Outline2D> for Triangles<P2> {
     type Error = anyhow::Error;
 
-    // FIXME: this does unconstrained Delaunay Triangulation which
-    // means it's only valid for convex polygons.
-
     fn try_from(value: Outline2D) -> Result<Self, Self::Error> {
-        use spade::Triangulation;
+        Ok(Self(triangulate_rings(&[value.0])?))
+    }
+}
 
-        let mut tri = spade::DelaunayTriangulation::<spade::Point2<FP>>::new();
+/// An outline with holes in it
+#[derive(Clone, Debug)]
+pub struct Polygon2D {
+    pub outline: Outline2D,
+    pub holes: Vec<Outline2D>,
+}
 
-        for point in value.0 {
-            tri.insert(spade_from_p2(point))?;
+impl Polygon2D {
+    pub fn new(outline: Outline2D, holes: Vec<Outline2D>) -> Self {
+        Self {
+            outline,
+            holes,
         }
+    }
+}
 
-        let triangles: Vec<_> = tri.inner_faces()
-            .map(|f| Triangle(f.vertices().map(|v| spade_to_p2(*v.data()))))
+impl TryFrom<Polygon2D> for Triangles<P2> {
+    type Error = anyhow::Error;
+
+    fn try_from(value: Polygon2D) -> Result<Self, Self::Error> {
+        let rings: Vec<_> = std::iter::once(value.outline)
+            .chain(value.holes)
+            .map(|ring| ring.0)
             .collect();
 
-        Ok(Self(triangles))
+        Ok(Self(triangulate_rings(&rings)?))
     }
 }
+
+/// Constrained Delaunay Triangulation of the area enclosed by `rings`.
+///
+/// Every ring edge becomes a constraint, and a triangle is kept when reaching it from
+/// the outside requires crossing an odd number of constraints.
+/// The rings must not intersect each other or themselves.
+fn triangulate_rings(rings: &[Vec<P2>]) -> anyhow::Result<Vec<Triangle<P2>>> {
+    use spade::{ConstrainedDelaunayTriangulation, Triangulation};
+
+    let mut tri = ConstrainedDelaunayTriangulation::<spade::Point2<FP>>::new();
+
+    for ring in rings {
+        anyhow::ensure!(ring.len() >= 3, "Ring should have at least 3 points, got {}", ring.len());
+
+        let handles = ring.iter()
+            .map(|p| tri.insert(spade_from_p2(*p)))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        for (i, from) in handles.iter().enumerate() {
+            let to = handles[(i + 1) % handles.len()];
+
+            if *from == to {
+                continue;
+            }
+
+            anyhow::ensure!(tri.can_add_constraint(*from, to), "Ring edges should not intersect");
+            tri.add_constraint(*from, to);
+        }
+    }
+
+    // Walk the faces starting from the outside, flipping the parity on each constraint edge
+    let mut inside: Vec<Option<bool>> = vec![None; tri.num_all_faces()];
+    let mut queue = std::collections::VecDeque::new();
+
+    for face in tri.inner_faces() {
+        for edge in face.adjacent_edges() {
+            if edge.rev().face().is_outer() && inside[face.index()].is_none() {
+                inside[face.index()] = Some(edge.is_constraint_edge());
+                queue.push_back(face.fix());
+            }
+        }
+    }
+
+    while let Some(handle) = queue.pop_front() {
+        let face = tri.face(handle);
+        let face_inside = inside[face.index()].unwrap();
+
+        for edge in face.adjacent_edges() {
+            if let Some(neighbor) = edge.rev().face().as_inner() {
+                if inside[neighbor.index()].is_none() {
+                    inside[neighbor.index()] = Some(face_inside != edge.is_constraint_edge());
+                    queue.push_back(neighbor.fix());
+                }
+            }
+        }
+    }
+
+    let triangles = tri.inner_faces()
+        .filter(|f| inside[f.index()] == Some(true))
+        .map(|f| Triangle(f.vertices().map(|v| spade_to_p2(*v.data()))))
+        .collect();
+
+    Ok(triangles)
+}
@@ -0,0 +1,186 @@
+use cgmath::{InnerSpace, Quaternion, Rad, Rotation, Rotation3};
+
+use super::{Geometry2D, Geometry3D, EPSILON, FP, P3, V3};
+
+
+/// How the shape is oriented along the path of a sweep
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SweepFrames {
+    /// Follows the curvature of the path.
+    /// Flips on inflections, and keeps the previous orientation on straight parts.
+    Frenet,
+    /// Rotates as little as possible from one point of the path to the next.
+    #[default]
+    RotationMinimizing,
+}
+
+/// Orientation of the shape at a point of the path.
+/// X of the shape goes along `normal`, Y goes along `tangent × normal`.
+#[derive(Clone, Copy, Debug)]
+struct Frame {
+    tangent: V3,
+    normal: V3,
+}
+
+impl Frame {
+    fn binormal(&self) -> V3 {
+        self.tangent.cross(self.normal)
+    }
+}
+
+/// Any unit vector perpendicular to `v`, preferring the X axis
+fn perpendicular(v: V3) -> V3 {
+    let x = V3::unit_x();
+    let reference = if v.dot(x).abs() < 0.9 { x } else { V3::unit_y() };
+
+    (reference - v * reference.dot(v)).normalize()
+}
+
+
+impl Geometry2D {
+    /// Move the shape along the path, leaving a tube behind.
+    ///
+    /// The shape is kept perpendicular to the path, and is stretched at the corners
+    /// so that the walls of the tube keep their thickness.
+    /// A `closed` path connects its last point back to the first one.
+    pub fn sweep(&self, path: &[P3], frames: SweepFrames, closed: bool) -> anyhow::Result<Geometry3D> {
+        let mut path = path.to_vec();
+        path.dedup_by(|a, b| (*a - *b).magnitude() < EPSILON);
+
+        if closed && path.len() > 1 && (path[0] - path[path.len() - 1]).magnitude() < EPSILON {
+            path.pop();
+        }
+
+        anyhow::ensure!(
+            path.len() >= if closed { 3 } else { 2 },
+            "Path should have at least {} distinct points", if closed { 3 } else { 2 }
+        );
+
+        let n = path.len();
+
+        // Direction of the segment that starts at each point
+        let segments: Vec<V3> = (0..n).map(|i| {
+            let next = if closed { (i + 1) % n } else { (i + 1).min(n - 1) };
+            let prev = if closed || i + 1 < n { i } else { i - 1 };
+            (path[next] - path[prev]).normalize()
+        }).collect();
+
+        // Direction of the segment that ends at each point
+        let incoming: Vec<V3> = (0..n).map(|i| {
+            match (i, closed) {
+                (0, true) => segments[n - 1],
+                (0, false) => segments[0],
+                (i, _) => segments[i - 1],
+            }
+        }).collect();
+
+        let tangents: Vec<V3> = (0..n).map(|i| {
+            let sum = incoming[i] + segments[i];
+            if sum.magnitude() < EPSILON { segments[i] } else { sum.normalize() }
+        }).collect();
+
+        let frames = match frames {
+            SweepFrames::Frenet => frenet_frames(&tangents, &incoming, &segments),
+            SweepFrames::RotationMinimizing => rotation_minimizing_frames(&path, &tangents, closed),
+        };
+
+        let layers: Vec<Vec<P3>> = (0..n).map(|i| {
+            let frame = frames[i];
+
+            // At corners, the shape is on the plane between the segments and has to be longer in the direction of the turn
+            let turn = segments[i] - incoming[i];
+            let stretch = 1.0 / tangents[i].dot(segments[i]);
+
+            self.vertices.iter().map(|v| {
+                let mut offset = frame.normal * v.x + frame.binormal() * v.y;
+
+                if turn.magnitude() > EPSILON {
+                    let turn = turn.normalize();
+                    offset += turn * offset.dot(turn) * (stretch - 1.0);
+                }
+
+                path[i] + offset
+            }).collect()
+        }).collect();
+
+        Ok(self.stack_layers(&layers, closed))
+    }
+}
+
+
+fn frenet_frames(tangents: &[V3], incoming: &[V3], segments: &[V3]) -> Vec<Frame> {
+    let mut frames: Vec<Frame> = vec![];
+
+    for (i, tangent) in tangents.iter().enumerate() {
+        let curvature = segments[i] - incoming[i];
+        let curvature = curvature - tangent * curvature.dot(*tangent);
+
+        let normal = if curvature.magnitude() > EPSILON {
+            curvature.normalize()
+        } else if let Some(previous) = frames.last() {
+            // Straight part, keep the previous normal
+            let normal = previous.normal - tangent * previous.normal.dot(*tangent);
+            if normal.magnitude() > EPSILON { normal.normalize() } else { perpendicular(*tangent) }
+        } else {
+            perpendicular(*tangent)
+        };
+
+        frames.push(Frame { tangent: *tangent, normal });
+    }
+
+    // The start of the path might be straight, give it the first normal that comes from the curvature
+    if let Some(first_curved) = (0..tangents.len()).find(|i| (segments[*i] - incoming[*i]).magnitude() > EPSILON) {
+        for i in 0..first_curved {
+            let normal = frames[first_curved].normal;
+            let normal = normal - tangents[i] * normal.dot(tangents[i]);
+            if normal.magnitude() > EPSILON {
+                frames[i].normal = normal.normalize();
+            }
+        }
+    }
+
+    frames
+}
+
+/// Double reflection method, from "Computation of Rotation Minimizing Frames" by Wenping Wang et al.
+fn rotation_minimizing_frames(path: &[P3], tangents: &[V3], closed: bool) -> Vec<Frame> {
+    let n = path.len();
+
+    let reflect = |v: V3, axis: V3| {
+        let c = axis.dot(axis);
+        if c < EPSILON * EPSILON { v } else { v - axis * (2.0 / c * axis.dot(v)) }
+    };
+
+    let mut frames = vec![Frame { tangent: tangents[0], normal: perpendicular(tangents[0]) }];
+
+    let steps = if closed { n } else { n - 1 };
+
+    for i in 0..steps {
+        let current = frames[i];
+        let next = (i + 1) % n;
+
+        let v1 = path[next] - path[i];
+        let normal_l = reflect(current.normal, v1);
+        let tangent_l = reflect(current.tangent, v1);
+
+        let v2 = tangents[next] - tangent_l;
+        let normal = reflect(normal_l, v2).normalize();
+
+        frames.push(Frame { tangent: tangents[next], normal });
+    }
+
+    if closed {
+        // Going around the loop does not necessarily end up with the same normal, spread the difference
+        let end = frames.pop().unwrap();
+        let start = frames[0];
+
+        let angle = end.normal.cross(start.normal).dot(start.tangent).atan2(end.normal.dot(start.normal));
+
+        for (i, frame) in frames.iter_mut().enumerate() {
+            let rot = Quaternion::from_axis_angle(frame.tangent, Rad(angle * i as FP / n as FP));
+            frame.normal = rot.rotate_vector(frame.normal).normalize();
+        }
+    }
+
+    frames
+}
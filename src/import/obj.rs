@@ -0,0 +1,104 @@
+use std::io::Read;
+use anyhow::Context;
+
+use crate::geometry::{primitives::{Outline2D, Triangles, P2, P3}, Geometry3D};
+
+impl Geometry3D {
+    /// Read the vertices and the faces of a Wavefront OBJ file, everything else is ignored.
+    /// Faces with more than 3 vertices are triangulated.
+    pub fn from_obj<R: Read>(reader: &mut R) -> anyhow::Result<Self> {
+        let mut text = String::new();
+        reader.read_to_string(&mut text)?;
+
+        let mut vertices: Vec<P3> = vec![];
+        let mut triangles = vec![];
+
+        for (number, line) in text.lines().enumerate() {
+            let line = line.split('#').next().unwrap_or_default();
+            let mut tokens = line.split_whitespace();
+
+            let result = match tokens.next() {
+                Some("v") => read_vertex(tokens).map(|v| vertices.push(v)),
+                Some("f") => read_face(tokens, vertices.len()).map(|face| triangles.extend(triangulate(&face, &vertices))),
+                _ => Ok(()),
+            };
+
+            result.with_context(|| format!("OBJ line {}: {line:?}", number + 1))?;
+        }
+
+        Ok(Self::new(vertices, triangles))
+    }
+}
+
+fn read_vertex<'a, I: Iterator<Item = &'a str>>(mut tokens: I) -> anyhow::Result<P3> {
+    // The optional 4th coordinate, the weight, is ignored
+    let mut coordinate = || -> anyhow::Result<f64> {
+        let token = tokens.next().context("Vertex should have 3 coordinates")?;
+        token.parse().with_context(|| format!("Vertex coordinate should be a number, got {token:?}"))
+    };
+
+    Ok(P3::new(coordinate()?, coordinate()?, coordinate()?))
+}
+
+/// Indices of the vertices of the face, which can also have texture coordinates and normals like `1/2/3`
+fn read_face<'a, I: Iterator<Item = &'a str>>(tokens: I, count: usize) -> anyhow::Result<Vec<usize>> {
+    let face = tokens
+        .map(|token| {
+            let index: i64 = token.split('/').next().unwrap_or_default().parse()
+                .with_context(|| format!("Face vertex should be an index, got {token:?}"))?;
+
+            // Indices start at 1, negative ones go back from the last vertex
+            let index = if index < 0 { count as i64 + index } else { index - 1 };
+
+            anyhow::ensure!(0 <= index && index < count as i64, "Face vertex {token} should be one of the {count} vertices");
+            Ok(index as usize)
+        })
+        .collect::<anyhow::Result<Vec<_>>>()?;
+
+    anyhow::ensure!(face.len() >= 3, "Face should have at least 3 vertices, got {}", face.len());
+
+    Ok(face)
+}
+
+/// Split the face into triangles going around the same way.
+/// The face is looked at along its normal, so it can be concave.
+fn triangulate(face: &[usize], vertices: &[P3]) -> Vec<[usize; 3]> {
+    let fan = || -> Vec<[usize; 3]> {
+        (1..face.len() - 1).map(|i| [face[0], face[i], face[i + 1]]).collect()
+    };
+
+    if face.len() == 3 {
+        return fan();
+    }
+
+    // Newell's normal, which works for any simple polygon
+    let mut normal = [0.0; 3];
+    for (i, a) in face.iter().enumerate() {
+        let (a, b) = (vertices[*a], vertices[face[(i + 1) % face.len()]]);
+        normal[0] += (a.y - b.y) * (a.z + b.z);
+        normal[1] += (a.z - b.z) * (a.x + b.x);
+        normal[2] += (a.x - b.x) * (a.y + b.y);
+    }
+
+    // Drop the coordinate along which the normal is the largest, keeping the face counter-clockwise
+    let dropped = (0..3).max_by(|a, b| normal[*a].abs().total_cmp(&normal[*b].abs())).unwrap();
+    let [u, v] = if normal[dropped] >= 0.0 {
+        [(dropped + 1) % 3, (dropped + 2) % 3]
+    } else {
+        [(dropped + 2) % 3, (dropped + 1) % 3]
+    };
+
+    let projected: Vec<P2> = face.iter().map(|i| P2::new(vertices[*i][u], vertices[*i][v])).collect();
+
+    let Ok(triangles) = Triangles::<P2>::try_from(Outline2D(projected.clone())) else {
+        return fan();
+    };
+
+    // The triangulation keeps the positions, so they lead back to the vertices
+    let index = |p: &P2| projected.iter().position(|q| q == p).map(|i| face[i]);
+
+    triangles.iter()
+        .map(|t| Some([index(&t[0])?, index(&t[1])?, index(&t[2])?]))
+        .collect::<Option<Vec<_>>>()
+        .unwrap_or_else(fan)
+}
@@ -0,0 +1,98 @@
+use std::io::Read;
+use anyhow::Context;
+use byteorder::{LittleEndian, ReadBytesExt};
+
+use crate::geometry::primitives::{Triangle, Triangles, P3};
+
+impl Triangles<P3> {
+    /// Read an STL file, either binary or ASCII
+    pub fn from_stl<R: Read>(reader: &mut R) -> anyhow::Result<Self> {
+        let mut data = vec![];
+        reader.read_to_end(&mut data)?;
+
+        if is_binary(&data) {
+            read_binary(&data)
+        } else {
+            read_ascii(std::str::from_utf8(&data).context("ASCII STL should be valid UTF-8")?)
+        }
+    }
+}
+
+/// ASCII files start with `solid`, but the headers of some binary files do too,
+/// so binary files are recognized by having the size that their triangle count says
+fn is_binary(data: &[u8]) -> bool {
+    if data.len() >= 84 {
+        let count = u32::from_le_bytes([data[80], data[81], data[82], data[83]]) as usize;
+
+        if data.len() == 84 + 50 * count {
+            return true;
+        }
+    }
+
+    !data.trim_ascii_start().starts_with(b"solid")
+}
+
+fn read_binary(data: &[u8]) -> anyhow::Result<Triangles<P3>> {
+    anyhow::ensure!(data.len() >= 84, "Binary STL should have at least 84 bytes, got {}", data.len());
+
+    // Skip 80-byte header
+    let mut reader = &data[80..];
+
+    let count = reader.read_u32::<LittleEndian>()? as usize;
+    anyhow::ensure!(
+        reader.len() == 50 * count,
+        "Binary STL with {count} triangles should have {} bytes, got {}", 84 + 50 * count, data.len()
+    );
+
+    let mut triangles = Vec::with_capacity(count);
+
+    for _ in 0..count {
+        // Normals are computed from the points anyway
+        for _ in 0..3 {
+            reader.read_f32::<LittleEndian>()?;
+        }
+
+        let mut point = || -> std::io::Result<P3> {
+            let x = reader.read_f32::<LittleEndian>()?;
+            let y = reader.read_f32::<LittleEndian>()?;
+            let z = reader.read_f32::<LittleEndian>()?;
+            Ok(P3::new(x as f64, y as f64, z as f64))
+        };
+
+        triangles.push(Triangle::from_points([point()?, point()?, point()?]));
+
+        // Attributes are not used
+        reader.read_u16::<LittleEndian>()?;
+    }
+
+    Ok(Triangles::new(triangles))
+}
+
+fn read_ascii(text: &str) -> anyhow::Result<Triangles<P3>> {
+    let mut triangles = vec![];
+    let mut points = vec![];
+
+    let mut tokens = text.split_whitespace();
+
+    while let Some(token) = tokens.next() {
+        match token {
+            "vertex" => {
+                let mut coordinate = || -> anyhow::Result<f64> {
+                    let token = tokens.next().context("Vertex should have 3 coordinates")?;
+                    token.parse().with_context(|| format!("Vertex coordinate should be a number, got {token:?}"))
+                };
+
+                points.push(P3::new(coordinate()?, coordinate()?, coordinate()?));
+            }
+            "endloop" => {
+                anyhow::ensure!(points.len() == 3, "Facet should have 3 vertices, got {}", points.len());
+
+                triangles.push(Triangle::from_points([points[0], points[1], points[2]]));
+                points.clear();
+            }
+            _ => {}
+        }
+    }
+
+    Ok(Triangles::new(triangles))
+}
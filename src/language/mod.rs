@@ -1,13 +1,39 @@
-use cgmath::Deg;
+use cgmath::{Deg, Matrix, Matrix3, Matrix4};
+use clap::ValueEnum;
 use mlua::{prelude::*, UserData};
 
-use crate::prelude::*;
+use crate::{app::OutputFormat, geometry::{extrude::LinearExtrusion, offset::Join, primitives::{Triangles, P2, P3, V2}, sweep::SweepFrames, IndexedTriangles}, prelude::*};
 
 
 impl UserData for App {
     fn add_methods<'lua, M: LuaUserDataMethods<'lua, Self>>(ms: &mut M) {
-        ms.add_method("output", |_, this, geometry: Geometry3D| {
-            Ok(this.run(geometry))
+        // Either a geometry or a table of named parts like `{ base = base, lid = lid }`.
+        // Options override the command line, like `{ format = "stl-ascii", name = "bracket" }`
+        ms.add_method("output", |lua, this, (geometry, options): (LuaValue, Option<LuaTable>)| {
+            let mut output = this.output_options();
+
+            if let Some(options) = options {
+                if let Some(format) = options.get::<_, Option<String>>("format")? {
+                    output.format = OutputFormat::from_str(&format, true).map_err(LuaError::RuntimeError)?;
+                }
+                if let Some(name) = options.get("name")? {
+                    output.name = name;
+                }
+            }
+
+            match geometry {
+                LuaValue::Table(parts) => {
+                    let mut parts = parts.pairs::<String, Geometry3D>().collect::<LuaResult<Vec<_>>>()?;
+                    parts.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+                    this.run_parts(parts, &output);
+                }
+                geometry => {
+                    this.run_with(Geometry3D::from_lua(geometry, lua)?, &output);
+                }
+            }
+
+            Ok(())
         });
     }
 }
@@ -27,17 +53,72 @@ impl UserData for Geometry2D {
             Ok(this.rotate(Deg(angle).into()))
         });
 
-        ms.add_method("extrude_linear", |_, this, extent: f64| {
-            Ok(this.extrude_linear(extent))
+        // Rows of a 3x3 affine matrix, like `{ { 1, 0.5, 0 }, { 0, 1, 0 }, { 0, 0, 1 } }`
+        ms.add_method("transform", |_, this, rows: [[FP; 3]; 3]| {
+            check_affine(&rows)?;
+            Ok(this.transform(Matrix3::from(rows).transpose()))
+        });
+
+        ms.add_method("offset", |_, this, (delta, join): (FP, Option<Join>)| {
+            Ok(this.offset(delta, join.unwrap_or_default()))
+        });
+
+        ms.add_method("extrude_linear", |_, this, params: LinearExtrusion| {
+            Ok(this.extrude_linear_with(&params))
+        });
+
+        ms.add_method("rotate_extrude", |_, this, (angle, segments): (FP, usize)| {
+            this.rotate_extrude(Deg(angle).into(), segments).map_err(LuaError::external)
         });
 
-        ms.add_method("concat", |_, this, other: Self| {
+        ms.add_method("sweep", |_, this, (path, options): (Vec<[FP; 3]>, Option<LuaTable>)| {
+            let path: Vec<P3> = path.into_iter().map(P3::from).collect();
+
+            let mut frames = SweepFrames::default();
+            let mut closed = false;
+
+            if let Some(options) = options {
+                match options.get::<_, Option<String>>("frames")?.as_deref() {
+                    None | Some("rotation_minimizing") => {}
+                    Some("frenet") => frames = SweepFrames::Frenet,
+                    Some(other) => {
+                        return Err(LuaError::RuntimeError(format!("unknown frames: {other}")));
+                    }
+                }
+
+                closed = options.get::<_, Option<bool>>("closed")?.unwrap_or(false);
+            }
+
+            this.sweep(&path, frames, closed).map_err(LuaError::external)
+        });
+
+        ms.add_method("concat", |_, this, other: LuaUserDataRef<'lua, Self>| {
             Ok(this.concat(&other))
         });
 
-        ms.add_method("union", |_, this, other: Self| {
+        ms.add_method("hull", |_, this, ()| {
+            this.hull().map_err(LuaError::external)
+        });
+
+        ms.add_method("minkowski", |_, this, other: LuaUserDataRef<'lua, Self>| {
+            this.minkowski(&other).map_err(LuaError::external)
+        });
+
+        ms.add_method("union", |_, this, other: LuaUserDataRef<'lua, Self>| {
             Ok(this.union(&other))
         });
+
+        ms.add_method("difference", |_, this, other: LuaUserDataRef<'lua, Self>| {
+            Ok(this.difference(&other))
+        });
+
+        ms.add_method("intersection", |_, this, other: LuaUserDataRef<'lua, Self>| {
+            Ok(this.intersection(&other))
+        });
+
+        ms.add_method("xor", |_, this, other: LuaUserDataRef<'lua, Self>| {
+            Ok(this.xor(&other))
+        });
     }
 }
 
@@ -55,8 +136,149 @@ impl<'lua> FromLua<'lua> for Geometry2D {
 }
 
 
+/// Either just the height, or a table like `{ height = 2, twist = 90, scale = { 0.5, 1 }, slices = 10 }`.
+/// The extrusion is centered, unless there is `center = false` (starts at z = 0) or `range = { from, to }`.
+/// The height should be positive, the slices at least 1 and the scale not negative. Without `slices`, twisted extrusions get one slice every 5 degrees.
+impl<'lua> FromLua<'lua> for LinearExtrusion {
+    fn from_lua(value: LuaValue<'lua>, lua: &'lua Lua) -> LuaResult<Self> {
+        let mut params = LinearExtrusion::default();
+
+        match value {
+            LuaValue::Table(table) => {
+                if let Some(height) = table.get("height")? {
+                    params.height = height;
+                }
+                if let Some(twist) = table.get::<_, Option<FP>>("twist")? {
+                    params.twist = Deg(twist).into();
+                }
+                match table.get("scale")? {
+                    LuaValue::Nil => {}
+                    scale @ LuaValue::Table(_) => {
+                        params.scale = <[FP; 2]>::from_lua(scale, lua)?.into();
+                    }
+                    scale => {
+                        let scale = FP::from_lua(scale, lua)?;
+                        params.scale = V2::new(scale, scale);
+                    }
+                }
+                match table.get("slices")? {
+                    Some(slices) => params.slices = slices,
+                    // Like OpenSCAD, twisted walls get a slice every 5 degrees, a single one would cut through the shape
+                    None => params.slices = (Deg::from(params.twist).0.abs() / 5.0).ceil().max(1.0) as usize,
+                }
+                if let Some(false) = table.get("center")? {
+                    params.bottom = Some(0.0);
+                }
+                if let Some([from, to]) = table.get::<_, Option<[FP; 2]>>("range")? {
+                    // The ends can be given either way round
+                    params.bottom = Some(from.min(to));
+                    params.height = (to - from).abs();
+                }
+            }
+            height => {
+                params.height = FP::from_lua(height, lua)?;
+            }
+        }
+
+        if params.height <= 0.0 {
+            return Err(LuaError::RuntimeError(format!("extrusion height should be positive, got {}", params.height)));
+        }
+        if params.slices < 1 {
+            return Err(LuaError::RuntimeError(format!("extrusion slices should be at least 1, got {}", params.slices)));
+        }
+        if params.scale.x < 0.0 || params.scale.y < 0.0 {
+            return Err(LuaError::RuntimeError(format!("extrusion scale should not be negative, got ({}, {})", params.scale.x, params.scale.y)));
+        }
+
+        Ok(params)
+    }
+}
+
+
+/// Either the name of the join, or a table like `{ join = "miter", limit = 3 }` or `{ join = "round", segments = 64 }`.
+/// Round joins are the default.
+impl<'lua> FromLua<'lua> for Join {
+    fn from_lua(value: LuaValue<'lua>, lua: &'lua Lua) -> LuaResult<Self> {
+        let (name, options) = match value {
+            LuaValue::Table(table) => (table.get::<_, Option<String>>("join")?, Some(table)),
+            name => (Some(String::from_lua(name, lua)?), None),
+        };
+
+        let option = |key: &str| -> LuaResult<Option<FP>> {
+            match &options {
+                Some(table) => table.get(key),
+                None => Ok(None),
+            }
+        };
+
+        match name.as_deref() {
+            None | Some("round") => Ok(Join::Round(option("segments")?.map_or(32, |s| s as usize))),
+            Some("miter") => Ok(Join::Miter(option("limit")?.unwrap_or(2.0))),
+            Some("square") => Ok(Join::Square),
+            Some(other) => Err(LuaError::RuntimeError(format!("unknown join: {other}"))),
+        }
+    }
+}
+
+
 impl UserData for Geometry3D {
+    fn add_methods<'lua, M: LuaUserDataMethods<'lua, Self>>(ms: &mut M) {
+        ms.add_method("translate", |_, this, vector: [FP; 3]| {
+            Ok(this.translate(vector.into()))
+        });
+
+        ms.add_method("scale", |_, this, vector: [FP; 3]| {
+            Ok(this.scale(vector.into()))
+        });
+
+        // Either `rotate({ x, y, z })` with angles around each axis, or `rotate(angle, { x, y, z })` around an axis
+        ms.add_method("rotate", |lua, this, (angles, axis): (LuaValue, Option<[FP; 3]>)| {
+            match axis {
+                Some(axis) => {
+                    let angle = FP::from_lua(angles, lua)?;
+                    Ok(this.rotate_axis(axis.into(), Deg(angle).into()))
+                }
+                None => {
+                    let [x, y, z] = <[FP; 3]>::from_lua(angles, lua)?;
+                    Ok(this.rotate_euler(Deg(x).into(), Deg(y).into(), Deg(z).into()))
+                }
+            }
+        });
 
+        ms.add_method("mirror", |_, this, normal: [FP; 3]| {
+            Ok(this.mirror(normal.into()))
+        });
+
+        // Rows of a 4x4 affine matrix
+        ms.add_method("transform", |_, this, rows: [[FP; 4]; 4]| {
+            check_affine(&rows)?;
+            Ok(this.transform(Matrix4::from(rows).transpose()))
+        });
+
+        ms.add_method("concat", |_, this, other: LuaUserDataRef<'lua, Self>| {
+            Ok(this.concat(&other))
+        });
+
+        ms.add_method("hull", |_, this, ()| {
+            this.hull().map_err(LuaError::external)
+        });
+
+        ms.add_method("minkowski", |_, this, other: LuaUserDataRef<'lua, Self>| {
+            this.minkowski(&other).map_err(LuaError::external)
+        });
+
+        ms.add_method("union", |_, this, other: LuaUserDataRef<'lua, Self>| {
+            Ok(this.union(&other))
+        });
+
+        ms.add_method("difference", |_, this, other: LuaUserDataRef<'lua, Self>| {
+            Ok(this.difference(&other))
+        });
+
+        ms.add_method("intersection", |_, this, other: LuaUserDataRef<'lua, Self>| {
+            Ok(this.intersection(&other))
+        });
+    }
 }
 
 impl<'lua> FromLua<'lua> for Geometry3D {
@@ -73,17 +295,120 @@ impl<'lua> FromLua<'lua> for Geometry3D {
 }
 
 
+/// Only affine matrices keep the points where they should be, others divide them by their `w` coordinate
+fn check_affine<const N: usize>(rows: &[[FP; N]; N]) -> LuaResult<()> {
+    let last = &rows[N - 1];
+
+    if last[..N - 1].iter().all(|x| *x == 0.0) && last[N - 1] == 1.0 {
+        Ok(())
+    } else {
+        Err(LuaError::RuntimeError(format!("transform matrix should be affine, with a last row of (0, .., 0, 1), got {last:?}")))
+    }
+}
+
+
+/// All the geometries in one, they should be of the same type
+fn concat_all<Point: Clone + std::fmt::Debug + 'static>(geometries: &[LuaAnyUserData]) -> LuaResult<IndexedTriangles<Point>> {
+    let mut all = IndexedTriangles::new(vec![], vec![]);
+
+    for geometry in geometries {
+        all = all.concat(&*geometry.borrow::<IndexedTriangles<Point>>()?);
+    }
+
+    Ok(all)
+}
+
+
 pub fn lua(app: App) -> LuaResult<()> {
     let lua = Lua::new();
     let source = std::fs::read_to_string(&app.args.file).expect("Can't read that file :(");
 
+    // Files are looked up next to the script
+    let directory = app.args.file.parent().map(|p| p.to_path_buf()).unwrap_or_default();
+
     lua.globals().set("app", app)?;
 
     let f_circle = lua.create_function(|_, sides| Ok(Geometry2D::circle(sides)))?;
+    let f_rect = lua.create_function(|_, (width, height, centered): (FP, FP, Option<bool>)| {
+        Geometry2D::rect(width, height, centered.unwrap_or(false)).map_err(LuaError::external)
+    })?;
+    let f_rounded_rect = lua.create_function(|_, (width, height, radius, segments)| {
+        Geometry2D::rounded_rect(width, height, radius, segments).map_err(LuaError::external)
+    })?;
+    let f_ellipse = lua.create_function(|_, (radius_x, radius_y, segments)| {
+        Geometry2D::ellipse(radius_x, radius_y, segments).map_err(LuaError::external)
+    })?;
+    let f_polygon = lua.create_function(|_, points: Vec<[FP; 2]>| {
+        Geometry2D::polygon(points.into_iter().map(P2::from).collect()).map_err(LuaError::external)
+    })?;
     let f_sphere = lua.create_function(|_, subdivisions| Ok(Geometry3D::sphere(subdivisions)))?;
 
+    let f_cube = lua.create_function(|_, (size, centered): (FP, Option<bool>)| {
+        Ok(Geometry3D::cube(size, centered.unwrap_or(false)))
+    })?;
+    let f_cuboid = lua.create_function(|_, (size, centered): ([FP; 3], Option<bool>)| {
+        Ok(Geometry3D::cuboid(size.into(), centered.unwrap_or(false)))
+    })?;
+    let f_cylinder = lua.create_function(|_, (height, radius_bottom, radius_top, segments, centered): (FP, FP, FP, usize, Option<bool>)| {
+        Geometry3D::cylinder(height, radius_bottom, radius_top, segments, centered.unwrap_or(false)).map_err(LuaError::external)
+    })?;
+    let f_torus = lua.create_function(|_, (major_radius, minor_radius, major_segments, minor_segments)| {
+        Geometry3D::torus(major_radius, minor_radius, major_segments, minor_segments).map_err(LuaError::external)
+    })?;
+    let f_loft = lua.create_function(|_, (profiles, heights): (Vec<Geometry2D>, Vec<FP>)| {
+        Geometry3D::loft(&profiles, &heights).map_err(LuaError::external)
+    })?;
+    // Hull of all the given geometries, either all 2D or all 3D, like `hull(a, b, c)`
+    let f_hull = lua.create_function(|lua, geometries: mlua::Variadic<LuaAnyUserData>| {
+        let Some(first) = geometries.first() else {
+            return Err(LuaError::RuntimeError("hull needs at least one geometry".into()));
+        };
+
+        if first.is::<Geometry2D>() {
+            let hull = concat_all::<P2>(&geometries)?.hull().map_err(LuaError::external)?;
+            lua.create_userdata(hull)
+        } else {
+            let hull = concat_all::<P3>(&geometries)?.hull().map_err(LuaError::external)?;
+            lua.create_userdata(hull)
+        }
+    })?;
+
+    let f_minkowski = lua.create_function(|lua, (a, b): (LuaAnyUserData, LuaAnyUserData)| {
+        if a.is::<Geometry2D>() {
+            let sum = a.borrow::<Geometry2D>()?.minkowski(&*b.borrow::<Geometry2D>()?).map_err(LuaError::external)?;
+            lua.create_userdata(sum)
+        } else {
+            let sum = a.borrow::<Geometry3D>()?.minkowski(&*b.borrow::<Geometry3D>()?).map_err(LuaError::external)?;
+            lua.create_userdata(sum)
+        }
+    })?;
+    let stl_directory = directory.clone();
+    let f_import_stl = lua.create_function(move |_, path: String| {
+        let mut file = std::fs::File::open(stl_directory.join(path)).map_err(LuaError::external)?;
+        let triangles = Triangles::from_stl(&mut file).map_err(|e| LuaError::RuntimeError(format!("{e:#}")))?;
+        Ok(Geometry3D::from(triangles))
+    })?;
+    let f_import_obj = lua.create_function(move |_, path: String| {
+        let mut file = std::fs::File::open(directory.join(path)).map_err(LuaError::external)?;
+        // With the causes, which say what is wrong on the line
+        Geometry3D::from_obj(&mut file).map_err(|e| LuaError::RuntimeError(format!("{e:#}")))
+    })?;
+
     lua.globals().set("circle", f_circle)?;
+    lua.globals().set("rect", f_rect)?;
+    lua.globals().set("rounded_rect", f_rounded_rect)?;
+    lua.globals().set("ellipse", f_ellipse)?;
+    lua.globals().set("polygon", f_polygon)?;
     lua.globals().set("sphere", f_sphere)?;
+    lua.globals().set("cube", f_cube)?;
+    lua.globals().set("cuboid", f_cuboid)?;
+    lua.globals().set("cylinder", f_cylinder)?;
+    lua.globals().set("torus", f_torus)?;
+    lua.globals().set("loft", f_loft)?;
+    lua.globals().set("hull", f_hull)?;
+    lua.globals().set("minkowski", f_minkowski)?;
+    lua.globals().set("import_stl", f_import_stl)?;
+    lua.globals().set("import_obj", f_import_obj)?;
 
     lua.load(source).exec()?;
 
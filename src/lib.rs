@@ -1,6 +1,7 @@
 pub mod app;
 pub mod export;
 pub mod geometry;
+pub mod import;
 pub mod language;
 pub mod render;
 